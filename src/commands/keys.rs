@@ -0,0 +1,72 @@
+use log::warn;
+
+use crate::services::keys::KeyService;
+use crate::settings::Settings;
+use crate::tools::keys;
+
+
+/// Uso do comando administrativo de chaves.
+pub const USAGE: &str = "usage: easy_mdlwr keys <list | promote <kid> [--retire-after <seconds>] | retire <kid>>";
+
+
+/// Executa o comando `keys`.
+/// `promote` torna a chave ativa e agenda a aposentadoria das anteriores,
+/// por padrão após a validade do token de acesso.
+pub async fn run(args: &[String], service: KeyService, settings: &Settings) -> Result<(), String> {
+    // Parte do estado gravado, como faria o servidor.
+    if !service.sync().await {
+        return Err("can not load signing keys".to_string());
+    }
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["list"] => list(&service).await,
+        ["promote", kid] => promote(&service, kid, settings.jwt_ttl, settings).await,
+        ["promote", kid, "--retire-after", seconds] => {
+            let retire_after = seconds
+                .parse::<u64>()
+                .map_err(|e| format!("invalid --retire-after {}: {}", seconds, e))?;
+
+            promote(&service, kid, retire_after, settings).await
+        },
+        ["retire", kid] => service.retire(kid).await.map_err(|e| e.to_string()),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+
+/// Mostra o estado das chaves registradas e das disponíveis no disco.
+async fn list(service: &KeyService) -> Result<(), String> {
+    let states = service.list().await.ok_or("can not list signing keys")?;
+    let available = keys::available();
+    let active = keys::active().map(|key| key.kid.clone()).unwrap_or_default();
+
+    for state in states.iter() {
+        let retire_at = state.retire_at
+            .and_then(|at| at.try_to_rfc3339_string().ok())
+            .unwrap_or_else(|| "-".to_string());
+        let loaded = if available.contains(&state.kid) { "" } else { " (missing)" };
+
+        println!("{}\t{}\t{}{}", state.kid, state.status, retire_at, loaded);
+    }
+
+    // Chaves no disco ainda sem registro.
+    for kid in available.iter().filter(|kid| !states.iter().any(|state| &state.kid == *kid)) {
+        let status = if *kid == active { "active" } else { "unregistered" };
+
+        println!("{}\t{}\t-", kid, status);
+    }
+
+    Ok(())
+}
+
+
+async fn promote(service: &KeyService, kid: &str, retire_after: u64, settings: &Settings) -> Result<(), String> {
+    if retire_after < settings.jwt_ttl {
+        warn!(
+            "Retiring previous keys in {}s, before `jwt_ttl` ({}s): live tokens will be rejected.",
+            retire_after, settings.jwt_ttl,
+        );
+    }
+
+    service.promote(kid, retire_after).await.map_err(|e| e.to_string())
+}
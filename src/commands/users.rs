@@ -0,0 +1,79 @@
+use std::env;
+use std::io::{self, BufRead};
+
+use bson::oid::ObjectId;
+use log::info;
+use mongodb::bson::DateTime;
+
+use crate::models::users::UserModel;
+use crate::services::users::UserService;
+use crate::tools::hasher;
+use crate::views::payloads::CreateUserPayload;
+
+
+/// Uso do comando administrativo de usuários.
+pub const USAGE: &str = "usage: easy_mdlwr users create-superuser <username> <email>";
+
+/// Variável com a senha do novo superusuário.
+/// Sem ela, a senha é lida da primeira linha da entrada padrão.
+const PASSWORD_ENV: &str = "EASY_MDLWR_SUPERUSER_PASSWORD";
+
+
+/// Executa o comando `users`.
+/// `create-superuser` cadastra o primeiro administrador de um banco vazio,
+/// já que as rotas de usuários exigem a permissão `users`.
+pub async fn run(args: &[String], service: UserService) -> Result<(), String> {
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["create-superuser", username, email] => create_superuser(&service, username, email).await,
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+
+async fn create_superuser(service: &UserService, username: &str, email: &str) -> Result<(), String> {
+    // A senha não vai na linha de comando, visível na lista de processos.
+    let password = match env::var(PASSWORD_ENV) {
+        Ok(value) => value,
+        Err(_) => {
+            let mut line = String::new();
+
+            io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .map_err(|e| format!("can not read password: {}", e))?;
+
+            line.trim_end_matches(['\r', '\n']).to_string()
+        },
+    };
+
+    let payload = CreateUserPayload {
+        username: username.to_string(),
+        email: email.to_string(),
+        password,
+        first_name: username.to_string(),
+        last_name: String::new(),
+        is_active: true,
+        is_superuser: true,
+    };
+
+    payload.validate()?;
+
+    let password = hasher::hash_password(&payload.password).ok_or("can not hash password")?;
+    let user = UserModel {
+        _id: ObjectId::new(),
+        username: payload.username,
+        email: payload.email,
+        password,
+        first_name: payload.first_name,
+        last_name: payload.last_name,
+        is_active: payload.is_active,
+        is_superuser: payload.is_superuser,
+        created_at: DateTime::now(),
+        last_login: None,
+    };
+
+    service.create(&user).await.map_err(|e| e.to_string())?;
+
+    info!("Superuser {} is ready.", &user.username);
+    Ok(())
+}
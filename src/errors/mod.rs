@@ -0,0 +1,212 @@
+use std::fmt;
+
+use actix_web::http::header::{RETRY_AFTER, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+
+use crate::middlewares::request_id;
+use crate::services::authorization::{AccessDenied, Action};
+
+
+/// Tipo de conteúdo das respostas de erro, conforme a RFC 7807.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+
+/// Erro comum do serviço.
+/// Toda falha vira uma resposta `application/problem+json` com código estável.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    /// O recurso informado não existe.
+    NotFound(&'static str),
+    /// O registro viola um índice único.
+    Conflict(&'static str),
+    /// Os dados enviados são inválidos.
+    Validation(String),
+    /// Credencial ausente, inválida ou vencida.
+    Unauthorized {
+        code: &'static str,
+        detail: String,
+    },
+    /// O usuário não pode executar a ação.
+    Forbidden {
+        code: &'static str,
+        detail: String,
+    },
+    /// Falta ao usuário a permissão exigida, informada ao cliente.
+    PermissionDenied(AccessDenied),
+    /// Tentativas demais, com os segundos até a próxima.
+    TooManyRequests(u64),
+    /// O micro serviço não respondeu ou respondeu de forma inválida.
+    Upstream {
+        timeout: bool,
+    },
+    /// O serviço não pode atender a chamada pela configuração atual.
+    Unavailable {
+        code: &'static str,
+        detail: String,
+    },
+    /// Falha ao consultar ou gravar no banco de dados.
+    Database,
+    /// Falha interna, sem detalhes para o cliente.
+    Internal(&'static str),
+} impl AppError {
+    /// Usuário inexistente ou senha incorreta.
+    /// A mensagem não revela se a conta existe.
+    pub fn invalid_credentials() -> Self {
+        AppError::Unauthorized {
+            code: "invalid_credentials",
+            detail: "Invalid username or password.".to_string(),
+        }
+    }
+
+    /// Token de acesso ausente ou recusado.
+    pub fn invalid_token(detail: &str) -> Self {
+        AppError::Unauthorized {
+            code: "invalid_token",
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Rota que exige autenticação acessada de forma anônima.
+    pub fn authentication_required() -> Self {
+        AppError::Unauthorized {
+            code: "authentication_required",
+            detail: "Authentication required.".to_string(),
+        }
+    }
+
+    /// Conta desativada.
+    pub fn inactive_account() -> Self {
+        AppError::Forbidden {
+            code: "inactive_account",
+            detail: "Account is inactive.".to_string(),
+        }
+    }
+
+    /// Apenas superusuários concedem o acesso irrestrito
+    /// e alteram contas de superusuários.
+    pub fn superuser_required() -> Self {
+        AppError::Forbidden {
+            code: "superuser_required",
+            detail: "Only superusers can grant superuser access or manage superuser accounts.".to_string(),
+        }
+    }
+
+    /// Sem a chave interna, os micro serviços não autenticariam a chamada.
+    pub fn internal_token_unavailable() -> Self {
+        AppError::Unavailable {
+            code: "internal_token_unavailable",
+            detail: "Micro services are unavailable: the internal token signing key is not configured.".to_string(),
+        }
+    }
+
+    /// Código estável do erro, para uso dos clientes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Validation(_) => "validation_error",
+            AppError::Unauthorized { code, .. } => code,
+            AppError::Forbidden { code, .. } => code,
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::TooManyRequests(_) => "too_many_attempts",
+            AppError::Upstream { timeout: true } => "upstream_timeout",
+            AppError::Upstream { timeout: false } => "upstream_unavailable",
+            AppError::Unavailable { code, .. } => code,
+            AppError::Database => "database_unavailable",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+} impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(name) => write!(f, "{} not found", name),
+            AppError::Conflict(name) => write!(f, "{} already exists", name),
+            AppError::Validation(detail) => write!(f, "{}", detail),
+            AppError::Unauthorized { detail, .. } => write!(f, "{}", detail),
+            AppError::Forbidden { detail, .. } => write!(f, "{}", detail),
+            AppError::PermissionDenied(denied) => write!(
+                f,
+                "{} Required {} on {}.",
+                denied.reason, denied.action, denied.permission,
+            ),
+            AppError::TooManyRequests(_) => write!(f, "too many attempts, try again later"),
+            AppError::Upstream { timeout: true } => write!(f, "upstream service timed out"),
+            AppError::Upstream { timeout: false } => write!(f, "upstream service unavailable"),
+            AppError::Unavailable { detail, .. } => write!(f, "{}", detail),
+            AppError::Database => write!(f, "database unavailable"),
+            AppError::Internal(detail) => write!(f, "{}", detail),
+        }
+    }
+} impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden { .. } | AppError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Upstream { timeout: true } => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Upstream { timeout: false } => StatusCode::BAD_GATEWAY,
+            AppError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Database => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let mut response = HttpResponse::build(status);
+
+        match self {
+            AppError::Unauthorized { .. } => {
+                response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+            },
+            AppError::TooManyRequests(retry_after) => {
+                response.insert_header((RETRY_AFTER, retry_after.to_string()));
+            },
+            _ => (),
+        };
+
+        // A recusa de acesso leva a permissão e a ação exigidas.
+        let (permission, action) = match self {
+            AppError::PermissionDenied(denied) => (Some(denied.permission.clone()), Some(denied.action)),
+            _ => (None, None),
+        };
+        let problem = Problem {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail: self.to_string(),
+            code: self.code(),
+            request_id: request_id::current(),
+            permission,
+            action,
+        };
+
+        response
+            .content_type(PROBLEM_JSON)
+            .json(problem)
+    }
+}
+
+
+/// Corpo das respostas de erro, conforme a RFC 7807.
+/// `permission` e `action` são membros de extensão das recusas de acesso.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+}
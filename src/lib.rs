@@ -1,10 +1,29 @@
-mod models;
-mod settings;
-mod services;
-mod views;
-mod tools;
+pub mod commands;
+pub mod errors;
+pub mod middlewares;
+pub mod models;
+pub mod repositories;
+pub mod settings;
+pub mod services;
+pub mod views;
+pub mod tools;
 
-use self::services::MongoService;
+use actix_web::web;
+
+use self::repositories::Storage;
+use self::repositories::mongo::MongoService;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use self::repositories::sql::SqlStorage;
+use self::services::authorization::AuthorizationService;
+use self::services::groups::GroupService;
+use self::services::micro_services::RegistryService;
+use self::services::permissions::PermissionService;
+use self::services::proxy::ProxyService;
+use self::services::sessions::SessionService;
+use self::services::throttle::ThrottleService;
+use self::services::tokens::TokenService;
+use self::services::users::UserService;
+use self::settings::{Settings, StorageBackend};
 
 
 /// Inicia o log do serviço de consumo
@@ -22,10 +41,73 @@ pub fn init_service_log() {
 }
 
 
-/// Inicia o banco de dados do serviço.
-async fn init_database() {
-    // Instância o serviço do mongo,
-    let service = MongoService::new().await;
-    // Migra as coleções de dados.
-    service.migrate().await;
+/// Inicia o banco de dados do serviço conforme `storage_backend`.
+/// Retorna os repositórios para serem compartilhados entre as requisições.
+pub async fn init_storage(settings: &Settings) -> Storage {
+    match settings.storage_backend {
+        StorageBackend::Mongo => {
+            // Instância o serviço do mongo,
+            let service = MongoService::new(settings).await;
+            // Migra as coleções de dados.
+            service.migrate().await;
+
+            Storage::new(service)
+        },
+        #[cfg(any(feature = "postgres", feature = "sqlite"))]
+        StorageBackend::Postgres | StorageBackend::Sqlite => {
+            // A URL já foi exigida na validação das configurações.
+            let url = settings.database_url.as_deref().unwrap_or_default();
+            let service = SqlStorage::new(url, settings.database_max_connections).await;
+            // Cria as tabelas e os índices.
+            service.migrate().await;
+
+            Storage::new(service)
+        },
+        #[allow(unreachable_patterns)]
+        backend => panic!("Storage backend {} is not enabled in this build.", backend),
+    }
+}
+
+
+/// Serviços compartilhados por todos os workers.
+#[derive(Clone)]
+pub struct AppState {
+    users: web::Data<UserService>,
+    tokens: web::Data<TokenService>,
+    sessions: web::Data<SessionService>,
+    throttle: web::Data<ThrottleService>,
+    authorization: web::Data<AuthorizationService>,
+    permissions: web::Data<PermissionService>,
+    groups: web::Data<GroupService>,
+    registry: web::Data<RegistryService>,
+    proxy: web::Data<ProxyService>,
+} impl AppState {
+    pub fn new(storage: Storage, settings: &Settings) -> Self {
+        AppState {
+            users: web::Data::new(UserService::new(storage.clone())),
+            tokens: web::Data::new(TokenService::new(storage.clone())),
+            sessions: web::Data::new(SessionService::new(storage.clone())),
+            throttle: web::Data::new(ThrottleService::new(storage.clone())),
+            authorization: web::Data::new(AuthorizationService::new(storage.clone())),
+            permissions: web::Data::new(PermissionService::new(storage.clone())),
+            groups: web::Data::new(GroupService::new(storage.clone())),
+            registry: web::Data::new(RegistryService::new(storage.clone())),
+            proxy: web::Data::new(ProxyService::new(storage, settings)),
+        }
+    }
+
+    /// Registra os serviços, as rotas da API e o proxy para os micro serviços.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(self.users.clone())
+            .app_data(self.tokens.clone())
+            .app_data(self.sessions.clone())
+            .app_data(self.throttle.clone())
+            .app_data(self.authorization.clone())
+            .app_data(self.permissions.clone())
+            .app_data(self.groups.clone())
+            .app_data(self.registry.clone())
+            .app_data(self.proxy.clone())
+            .configure(views::routes)
+            .default_service(web::to(views::proxy::forward));
+    }
 }
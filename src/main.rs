@@ -0,0 +1,115 @@
+use std::env;
+use std::time::Duration;
+
+use actix_web::{middleware::Logger, rt, App, HttpServer};
+use log::{error, info};
+
+use easy_mdlwr::{commands, init_service_log, init_storage, AppState};
+use easy_mdlwr::middlewares::auth::Authentication;
+use easy_mdlwr::middlewares::cors::Cors;
+use easy_mdlwr::middlewares::request_id::RequestIdentifier;
+use easy_mdlwr::services::keys::KeyService;
+use easy_mdlwr::services::users::UserService;
+use easy_mdlwr::settings::Settings;
+use easy_mdlwr::tools::keys;
+
+
+/// Sobe o servidor HTTP com todas as rotas do serviço.
+/// Com `keys ...` ou `users ...` executa os comandos administrativos.
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    init_service_log();
+
+    // Carrega e valida as configurações: arquivo, ambiente e linha de comando.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (settings, args) = match Settings::init(&args) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Invalid settings: {}", e);
+            return Err(std::io::Error::other("invalid settings"));
+        }
+    };
+
+    // Recusa subir sem uma chave válida para assinar os tokens.
+    if keys::active().is_none() {
+        error!("Can not start without a valid JWT signing key.");
+        return Err(std::io::Error::other("invalid JWT signing key"));
+    }
+    if settings.internal_jwt_private_key_path.is_some() && keys::internal().is_none() {
+        error!("Can not start without a valid internal JWT signing key.");
+        return Err(std::io::Error::other("invalid internal JWT signing key"));
+    }
+
+    // Migra as coleções antes de aceitar requisições.
+    let storage = init_storage(settings).await;
+    let key_service = KeyService::new(storage.clone());
+
+    if args.first().map(String::as_str) == Some("keys") {
+        return commands::keys::run(&args[1..], key_service, settings)
+            .await
+            .map_err(|e| {
+                error!("{}", e);
+                std::io::Error::other(e)
+            });
+    }
+
+    if args.first().map(String::as_str) == Some("users") {
+        return commands::users::run(&args[1..], UserService::new(storage))
+            .await
+            .map_err(|e| {
+                error!("{}", e);
+                std::io::Error::other(e)
+            });
+    }
+
+    // Em produção, o proxy exige a chave interna para chamar os micro serviços.
+    let micro_services = match storage.micro_services.list().await {
+        Ok(value) => value.len(),
+        Err(e) => {
+            error!("Can not list micro services. Cause: {}", e);
+            return Err(std::io::Error::other("can not list micro services"));
+        }
+    };
+    if let Err(e) = settings.check_internal_key(micro_services) {
+        error!("Invalid settings: {}", e);
+        return Err(std::io::Error::other("invalid settings"));
+    }
+
+    // Carrega o chaveiro e o mantém sincronizado com o banco.
+    if !key_service.sync().await {
+        return Err(std::io::Error::other("invalid JWT keyring"));
+    }
+    let refresh = Duration::from_secs(settings.jwt_keyring_refresh.max(1));
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(refresh);
+
+        loop {
+            interval.tick().await;
+            key_service.sync().await;
+        }
+    });
+
+    // Serviços compartilhados por todos os workers.
+    let state = AppState::new(storage, settings);
+
+    info!("Starting server at {}:{}", &settings.host, settings.port);
+
+    let mut server = HttpServer::new(move || {
+        App::new()
+            .wrap(Authentication)
+            .wrap(Cors)
+            .wrap(RequestIdentifier)
+            // Formato padrão acrescido do identificador da requisição.
+            .wrap(Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#))
+            .configure(|cfg| state.configure(cfg))
+    });
+
+    if settings.workers > 0 {
+        server = server.workers(settings.workers);
+    }
+
+    server
+        .bind((settings.host.as_str(), settings.port))?
+        .run()
+        .await
+}
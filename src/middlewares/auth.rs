@@ -0,0 +1,192 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use futures_util::future::LocalBoxFuture;
+use log::{debug, error, warn};
+
+use crate::errors::AppError;
+use crate::models::tokens::Claims;
+use crate::models::users::UserModel;
+use crate::services::sessions::SessionService;
+use crate::services::tokens::TokenService;
+use crate::services::users::UserService;
+use crate::tools::hasher;
+
+
+/// Intervalo mínimo, em milissegundos, entre atualizações do último acesso da sessão.
+const LAST_SEEN_INTERVAL: i64 = 60_000;
+
+
+/// Identidade autenticada da requisição.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user: UserModel,
+    pub claims: Claims,
+}
+
+
+/// Middleware que valida o token `Bearer` da requisição.
+/// Quando válido, insere o `Principal` nas extensões da requisição.
+/// Requisições sem token seguem adiante, cabendo à rota exigir autenticação.
+pub struct Authentication;
+
+impl<S, B> Transform<S, ServiceRequest> for Authentication
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AuthenticationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+
+pub struct AuthenticationMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            // Sem cabeçalho, a requisição segue como anônima.
+            let header = match req.headers().get(AUTHORIZATION) {
+                Some(value) => value.to_str().unwrap_or_default().to_owned(),
+                None => {
+                    return service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body);
+                }
+            };
+
+            match authenticate(&req, &header).await {
+                Ok(principal) => {
+                    debug!("Authenticated user {}.", &principal.user.username);
+                    req.extensions_mut().insert(principal);
+                },
+                Err(e) => {
+                    let response = e.error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            service
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+
+/// Valida o cabeçalho `Authorization` e carrega o usuário dono do token.
+async fn authenticate(req: &ServiceRequest, header: &str) -> Result<Principal, AppError> {
+    let token = match header.strip_prefix("Bearer ") {
+        Some(value) => value.trim(),
+        None => return Err(AppError::invalid_token("Invalid authorization scheme.")),
+    };
+
+    let claims = match hasher::decode_jtw(token.to_owned()) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Refused access token, cause: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    let (service, tokens, sessions) = match (
+        req.app_data::<web::Data<UserService>>(),
+        req.app_data::<web::Data<TokenService>>(),
+        req.app_data::<web::Data<SessionService>>(),
+    ) {
+        (Some(users), Some(tokens), Some(sessions)) => (users, tokens, sessions),
+        _ => {
+            error!("UserService, TokenService or SessionService is not registered in application data.");
+            return Err(AppError::Internal("can not validate access token"));
+        }
+    };
+
+    // Recusa tokens revogados, inclusive quando não é possível consultar a lista.
+    if tokens.is_revoked(&claims.jti).await? {
+        return Err(AppError::invalid_token("Token has been revoked."));
+    }
+
+    let user_id = match ObjectId::parse_str(&claims.sub) {
+        Ok(value) => value,
+        Err(_) => return Err(AppError::invalid_token("Invalid token subject.")),
+    };
+    let user = match service.get_model_by_id(&user_id).await? {
+        Some(value) => value,
+        None => return Err(AppError::invalid_token("Invalid token subject.")),
+    };
+
+    if !user.is_active {
+        return Err(AppError::invalid_token("Account is inactive."));
+    }
+
+    // O token precisa ser o último emitido para uma sessão aberta do usuário.
+    let session = match ObjectId::parse_str(&claims.sid) {
+        Ok(id) => sessions.get(&id).await?,
+        Err(_) => None,
+    };
+    let session = match session {
+        Some(value) if value.user == user._id && hasher::secure_eq(value.jti.as_bytes(), claims.jti.as_bytes()) => value,
+        _ => return Err(AppError::invalid_token("Token is no longer valid.")),
+    };
+
+    // Evita gravar a cada requisição.
+    if DateTime::now().timestamp_millis() - session.last_seen.timestamp_millis() > LAST_SEEN_INTERVAL {
+        sessions.touch(&session._id).await;
+    }
+
+    Ok(Principal { user, claims })
+}
+
+
+/// Extrator que exige uma requisição autenticada.
+/// Basta declarar o parâmetro na rota para recusar acessos anônimos.
+#[derive(Debug, Clone)]
+pub struct Authenticated(pub Principal);
+
+impl FromRequest for Authenticated {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let principal = req
+            .extensions()
+            .get::<Principal>()
+            .cloned();
+
+        ready(match principal {
+            Some(value) => Ok(Authenticated(value)),
+            None => Err(AppError::authentication_required().into()),
+        })
+    }
+}
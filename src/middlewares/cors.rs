@@ -0,0 +1,133 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    HeaderValue,
+    ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS,
+    ACCESS_CONTROL_MAX_AGE,
+    ACCESS_CONTROL_REQUEST_HEADERS,
+    ACCESS_CONTROL_REQUEST_METHOD,
+    ORIGIN,
+    VARY,
+};
+use actix_web::http::Method;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use log::debug;
+
+use crate::settings::Settings;
+
+
+/// Métodos liberados para as origens aceitas.
+const ALLOWED_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
+/// Cabeçalhos liberados quando o navegador não informa quais usará.
+const ALLOWED_HEADERS: &str = "authorization, content-type";
+/// Cabeçalhos de resposta visíveis ao navegador.
+const EXPOSED_HEADERS: &str = "retry-after, www-authenticate, x-request-id";
+
+
+/// Middleware de CORS conforme `cors_allowed_origins`.
+/// Responde às requisições de preflight sem passar pela autenticação.
+pub struct Cors;
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+
+pub struct CorsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            // Sem origem aceita, a requisição segue sem cabeçalhos de CORS.
+            let origin = match req.headers().get(ORIGIN) {
+                Some(value) if is_allowed(value) => value.clone(),
+                _ => {
+                    return service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body);
+                }
+            };
+
+            let preflight = req.method() == Method::OPTIONS
+                && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+
+            if preflight {
+                debug!("Answering CORS preflight for {:?}.", &origin);
+
+                let headers = req
+                    .headers()
+                    .get(ACCESS_CONTROL_REQUEST_HEADERS)
+                    .cloned()
+                    .unwrap_or(HeaderValue::from_static(ALLOWED_HEADERS));
+                let response = HttpResponse::NoContent()
+                    .insert_header((ACCESS_CONTROL_ALLOW_ORIGIN, origin))
+                    .insert_header((ACCESS_CONTROL_ALLOW_METHODS, ALLOWED_METHODS))
+                    .insert_header((ACCESS_CONTROL_ALLOW_HEADERS, headers))
+                    .insert_header((ACCESS_CONTROL_MAX_AGE, Settings::get().cors_max_age.to_string()))
+                    .insert_header((VARY, "Origin"))
+                    .finish();
+
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut res = service.call(req).await?;
+            let headers = res.headers_mut();
+
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(EXPOSED_HEADERS));
+            headers.append(VARY, HeaderValue::from_static("Origin"));
+
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+
+/// Valida se a origem está entre as configuradas.
+fn is_allowed(origin: &HeaderValue) -> bool {
+    let origin = match origin.to_str() {
+        Ok(value) => value,
+        Err(_) => return false,
+    };
+
+    Settings::get()
+        .cors_allowed_origins
+        .iter()
+        .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+}
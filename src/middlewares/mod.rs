@@ -0,0 +1,4 @@
+pub mod auth;
+pub mod cors;
+pub mod permissions;
+pub mod request_id;
@@ -0,0 +1,100 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error, HttpMessage, ResponseError};
+use futures_util::future::LocalBoxFuture;
+use log::error;
+
+use crate::errors::AppError;
+use crate::middlewares::auth::Principal;
+use crate::services::authorization::{Action, AuthorizationService};
+
+
+/// Guarda de rota que exige uma permissão do usuário autenticado.
+/// A ação é deduzida do método HTTP da requisição.
+pub struct RequirePermission {
+    permission: Rc<String>,
+} impl RequirePermission {
+    pub fn new(permission: &str) -> Self {
+        RequirePermission {
+            permission: Rc::new(permission.to_string()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: Rc::clone(&self.permission),
+        }))
+    }
+}
+
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Rc<String>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let permission = Rc::clone(&self.permission);
+
+        Box::pin(async move {
+            let action = Action::from_method(req.method());
+            let principal = req
+                .extensions()
+                .get::<Principal>()
+                .cloned();
+            let principal = match principal {
+                Some(value) => value,
+                None => {
+                    let response = AppError::authentication_required().error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            let authorization = match req.app_data::<web::Data<AuthorizationService>>() {
+                Some(value) => value.clone(),
+                None => {
+                    error!("AuthorizationService is not registered in application data.");
+                    let response = AppError::Internal("can not evaluate user permissions").error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            if let Err(e) = authorization.can(&principal.user, &permission, action).await {
+                return Ok(req.into_response(e.error_response()).map_into_right_body());
+            }
+
+            service
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
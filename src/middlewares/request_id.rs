@@ -0,0 +1,105 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{Error, HttpMessage};
+use futures_util::future::LocalBoxFuture;
+use uuid::Uuid;
+
+
+/// Cabeçalho com o identificador da requisição.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+/// Tamanho máximo aceito para o identificador enviado pelo cliente.
+const MAX_LENGTH: usize = 128;
+
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+
+/// Identificador da requisição em atendimento, se houver.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+
+/// Identificador da requisição, guardado nas extensões.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+
+/// Middleware que identifica cada requisição.
+/// Reaproveita o `X-Request-Id` do cliente quando válido, senão gera um novo,
+/// e o devolve no cabeçalho da resposta.
+pub struct RequestIdentifier;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdentifier
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdentifierMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdentifierMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+
+pub struct RequestIdentifierMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdentifierMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let id = req
+                .headers()
+                .get(&REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| is_valid(value))
+                .map(str::to_string)
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+            req.extensions_mut().insert(RequestId(id.clone()));
+
+            // Os erros montados durante a requisição leem o identificador daqui.
+            let mut res = REQUEST_ID.scope(id.clone(), service.call(req)).await?;
+
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                res.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+
+/// Aceita apenas identificadores curtos e sem caracteres especiais,
+/// para não poluir os logs nem os cabeçalhos repassados.
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
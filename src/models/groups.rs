@@ -25,13 +25,26 @@ pub struct GroupModel {
 
 
 /// Estrutura para serialização dos dados via API Rest.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GroupSerialize {
-    #[serde(serialize_with="bson::serde_with::serialize_object_id_as_hex_string")]
+    #[serde(serialize_with="bson::serde_helpers::serialize_object_id_as_hex_string")]
     pub _id: ObjectId,
     pub name: String,
     pub permissions: Vec<PermissionSerialize>,
     pub actions: Actions,
-    #[serde(serialize_with="bson::serde_with::serialize_bson_datetime_as_rfc3339_string")]
+    #[serde(serialize_with="bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
     pub created_at: DateTime,
+} impl From<&GroupModel> for GroupSerialize {
+    fn from(group: &GroupModel) -> Self {
+        GroupSerialize {
+            _id: group._id,
+            name: group.name.clone(),
+            permissions: group.permissions
+                .iter()
+                .map(PermissionSerialize::from)
+                .collect(),
+            actions: group.actions.clone(),
+            created_at: group.created_at,
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+
+/// Chave pública no formato JWK (RFC 7517).
+#[derive(Debug, Clone, Serialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg: String,
+    pub kid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+
+/// Conjunto de chaves publicado em `/.well-known/jwks.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+
+/// Situação de uma chave no chaveiro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStatus {
+    /// Assina os novos tokens, apenas uma por vez.
+    Active,
+    /// Apenas valida os tokens já emitidos.
+    Verify,
+    /// Não é mais aceita.
+    Retired,
+} impl fmt::Display for KeyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeyStatus::Active => "active",
+            KeyStatus::Verify => "verify",
+            KeyStatus::Retired => "retired",
+        };
+
+        write!(f, "{}", name)
+    }
+} impl FromStr for KeyStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "active" => Ok(KeyStatus::Active),
+            "verify" => Ok(KeyStatus::Verify),
+            "retired" => Ok(KeyStatus::Retired),
+            _ => Err(format!("unknown key status {}", value)),
+        }
+    }
+}
+
+
+/// Estado de uma chave de assinatura persistido no banco de dados.
+/// O material da chave fica fora do banco, em `jwt_keys_dir`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SigningKeyModel {
+    pub _id: ObjectId,
+    pub kid: String,
+    pub status: KeyStatus,
+    pub created_at: DateTime,
+    /// Instante em que a chave deixa de validar tokens.
+    pub retire_at: Option<DateTime>,
+}
@@ -8,17 +8,32 @@ pub struct MicroServiceModel {
     pub _id: ObjectId,
     pub name: String,
     pub host: String,
+    /// Prefixos de rota atendidos pelo micro serviço.
+    #[serde(default)]
+    pub routes: Vec<String>,
     pub created_at: DateTime,
 }
 
 
 /// Objeto de serialização do microserviços na API Rest.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MicroServiceSerialize {
-    #[serde(serialize_with="bson::serde_with::serialize_object_id_as_hex_string")]
+    #[serde(serialize_with="bson::serde_helpers::serialize_object_id_as_hex_string")]
     pub _id: ObjectId,
     pub name: String,
     pub host: String,
-    #[serde(serialize_with="bson::serde_with::serialize_bson_datetime_as_rfc3339_string")]
+    #[serde(default)]
+    pub routes: Vec<String>,
+    #[serde(serialize_with="bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
     pub created_at: DateTime,
+} impl From<&MicroServiceModel> for MicroServiceSerialize {
+    fn from(micro_service: &MicroServiceModel) -> Self {
+        MicroServiceSerialize {
+            _id: micro_service._id,
+            name: micro_service.name.clone(),
+            host: micro_service.host.clone(),
+            routes: micro_service.routes.clone(),
+            created_at: micro_service.created_at,
+        }
+    }
 }
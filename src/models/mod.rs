@@ -3,3 +3,21 @@ pub mod permissions;
 pub mod groups;
 pub mod relationship;
 pub mod micro_services;
+pub mod tokens;
+pub mod keys;
+pub mod sessions;
+
+use mongodb::bson::DateTime;
+use serde::Serializer;
+
+
+/// Serializa datas opcionais no formato RFC 3339.
+pub fn serialize_optional_datetime<S>(value: &Option<DateTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(date) => bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -12,11 +12,19 @@ pub struct PermissionModel {
 
 
 /// Estrutura para serialização de dados na API Rest.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PermissionSerialize {
     #[serde(serialize_with = "bson::serde_helpers::serialize_object_id_as_hex_string")]
     pub _id: ObjectId,
     pub name: String,
     #[serde(serialize_with = "bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
     pub created_at: DateTime,
+} impl From<&PermissionModel> for PermissionSerialize {
+    fn from(permission: &PermissionModel) -> Self {
+        PermissionSerialize {
+            _id: permission._id,
+            name: permission.name.clone(),
+            created_at: permission.created_at,
+        }
+    }
 }
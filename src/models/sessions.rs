@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+
+/// Sessão de um usuário em um dispositivo.
+/// O `_id` também identifica a família dos tokens de renovação da sessão.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SessionModel {
+    pub _id: ObjectId,
+    pub user: ObjectId,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    /// Último token de acesso emitido para a sessão.
+    pub jti: String,
+    pub token_expires_at: DateTime,
+    pub created_at: DateTime,
+    pub last_seen: DateTime,
+    /// Vence junto com o token de renovação.
+    pub expires_at: DateTime,
+}
+
+
+/// Objeto para serialização das sessões via API Rest.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSerialize {
+    #[serde(serialize_with = "bson::serde_helpers::serialize_object_id_as_hex_string")]
+    pub _id: ObjectId,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(serialize_with = "bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
+    pub created_at: DateTime,
+    #[serde(serialize_with = "bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
+    pub last_seen: DateTime,
+    /// Sessão usada na requisição.
+    pub current: bool,
+} impl From<&SessionModel> for SessionSerialize {
+    fn from(session: &SessionModel) -> Self {
+        SessionSerialize {
+            _id: session._id,
+            user_agent: session.user_agent.clone(),
+            ip: session.ip.clone(),
+            created_at: session.created_at,
+            last_seen: session.last_seen,
+            current: false,
+        }
+    }
+}
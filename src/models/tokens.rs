@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+
+/// Claims assinadas no token JWT de acesso.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Claims {
+    /// ObjectId do usuário em hexadecimal.
+    pub sub: String,
+    pub username: String,
+    pub email: String,
+    pub iss: String,
+    pub aud: String,
+    /// Instantes em segundos desde a época Unix.
+    pub exp: u64,
+    pub iat: u64,
+    pub nbf: u64,
+    /// Identificador único do token.
+    pub jti: String,
+    /// Sessão, em hexadecimal, que originou o token.
+    pub sid: String,
+}
+
+
+/// Claims do token interno enviado aos micro serviços.
+/// Tem emissor e chave próprios e vale por poucos segundos.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InternalClaims {
+    /// ObjectId do usuário em hexadecimal.
+    pub sub: String,
+    pub username: String,
+    pub iss: String,
+    /// Nome do micro serviço de destino.
+    pub aud: String,
+    pub exp: u64,
+    pub iat: u64,
+    pub nbf: u64,
+    pub jti: String,
+    /// Permissões efetivas no formato `permissão:ação`.
+    pub permissions: Vec<String>,
+}
+
+
+/// Token de renovação persistido no banco de dados.
+/// Apenas o hash do token é gravado, o valor opaco fica com o cliente.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefreshTokenModel {
+    pub _id: ObjectId,
+    pub user: ObjectId,
+    /// Família de tokens gerada a partir de um mesmo login.
+    pub family: ObjectId,
+    pub token_hash: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    /// Preenchido quando o token é trocado por um novo.
+    pub rotated_at: Option<DateTime>,
+    pub revoked: bool,
+}
+
+
+/// Token de acesso revogado antes de vencer.
+/// Some do banco quando o token venceria, pelo índice TTL em `expires_at`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RevokedTokenModel {
+    pub _id: ObjectId,
+    pub jti: String,
+    pub user: ObjectId,
+    pub revoked_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+
+/// Contador de falhas de login por usuário ou por IP.
+/// Some do banco após um período sem falhas, pelo índice TTL em `expires_at`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginAttemptModel {
+    pub _id: ObjectId,
+    /// `user:<username>` ou `ip:<endereço>`.
+    pub key: String,
+    pub failures: u32,
+    pub last_failure: DateTime,
+    /// Novas tentativas são recusadas até este instante.
+    pub blocked_until: DateTime,
+    pub expires_at: DateTime,
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use mongodb::bson::{oid::ObjectId, DateTime};
 
+use crate::models::serialize_optional_datetime;
+
 
 /// Objeto para manipulação de dados no banco de dados.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -9,33 +11,99 @@ pub struct UserModel {
     pub username: String,
     pub email: String,
     pub password: String,
-    pub first_mame: String,
+    /// Documentos antigos gravaram o campo com o nome `first_mame`.
+    #[serde(alias = "first_mame")]
+    pub first_name: String,
     pub last_name: String,
     pub is_active: bool,
     pub is_superuser: bool,
-    pub token: Option<String>,
     pub created_at: DateTime,
     pub last_login: Option<DateTime>,
 }
 
 
 /// Objeto para serialização dos dados via API Rest.
+/// Nunca expõe a senha do usuário.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UserSerialize {
     #[serde(serialize_with = "bson::serde_helpers::serialize_object_id_as_hex_string")]
     pub _id: ObjectId,
     pub username: String,
     pub email: String,
-    pub password: String,
+    #[serde(alias = "first_mame")]
     pub first_name: String,
     pub last_name: String,
     pub is_active: bool,
     pub is_superuser: bool,
-    pub token: Option<String>,
     #[serde(serialize_with = "bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
     pub created_at: DateTime,
-    #[serde(serialize_with = "bson::serde_helpers::serialize_bson_datetime_as_rfc3339_string")]
-    pub last_login: DateTime,
+    #[serde(default, serialize_with = "serialize_optional_datetime")]
+    pub last_login: Option<DateTime>,
+} impl From<&UserModel> for UserSerialize {
+    fn from(user: &UserModel) -> Self {
+        UserSerialize {
+            _id: user._id,
+            username: user.username.clone(),
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+            is_active: user.is_active,
+            is_superuser: user.is_superuser,
+            created_at: user.created_at,
+            last_login: user.last_login,
+        }
+    }
+}
+
+
+/// Campos alterados de um usuário.
+/// Apenas os campos informados são gravados.
+#[derive(Debug, Clone, Default)]
+pub struct UserChanges {
+    pub username: Option<String>,
+    pub email: Option<String>,
+    /// Hash da nova senha.
+    pub password: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub is_active: Option<bool>,
+    pub is_superuser: Option<bool>,
+} impl UserChanges {
+    /// Valida se nenhum campo foi informado.
+    pub fn is_empty(&self) -> bool {
+        self.username.is_none()
+            && self.email.is_none()
+            && self.password.is_none()
+            && self.first_name.is_none()
+            && self.last_name.is_none()
+            && self.is_active.is_none()
+            && self.is_superuser.is_none()
+    }
+
+    /// Aplica as alterações sobre o usuário.
+    pub fn apply(&self, user: &mut UserModel) {
+        if let Some(value) = &self.username {
+            user.username = value.clone();
+        }
+        if let Some(value) = &self.email {
+            user.email = value.clone();
+        }
+        if let Some(value) = &self.password {
+            user.password = value.clone();
+        }
+        if let Some(value) = &self.first_name {
+            user.first_name = value.clone();
+        }
+        if let Some(value) = &self.last_name {
+            user.last_name = value.clone();
+        }
+        if let Some(value) = self.is_active {
+            user.is_active = value;
+        }
+        if let Some(value) = self.is_superuser {
+            user.is_superuser = value;
+        }
+    }
 }
 
 
@@ -43,4 +111,5 @@ pub struct UserSerialize {
 #[derive(Debug, Serialize)]
 pub struct Login {
     pub token: String,
+    pub refresh_token: String,
 }
@@ -0,0 +1,590 @@
+use std::cmp::Reverse;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::repositories::{
+    GroupRepository,
+    LoginAttemptRepository,
+    MicroServiceRepository,
+    PermissionRepository,
+    RelationshipRepository,
+    SessionRepository,
+    SigningKeyRepository,
+    TokenRepository,
+    UserRepository,
+};
+use crate::models::{
+    users::{UserChanges, UserModel},
+    permissions::PermissionModel,
+    groups::GroupModel,
+    micro_services::MicroServiceModel,
+    relationship::{UsersGroup, MicroServicePermission},
+    tokens::{LoginAttemptModel, RefreshTokenModel, RevokedTokenModel},
+    keys::{KeyStatus, SigningKeyModel},
+    sessions::SessionModel,
+};
+
+
+/// Coleções guardadas em memória.
+#[derive(Default)]
+struct Tables {
+    users: Vec<UserModel>,
+    permissions: Vec<PermissionModel>,
+    groups: Vec<GroupModel>,
+    micro_services: Vec<MicroServiceModel>,
+    users_groups: Vec<UsersGroup>,
+    micro_services_permission: Vec<MicroServicePermission>,
+    refresh_tokens: Vec<RefreshTokenModel>,
+    revoked_tokens: Vec<RevokedTokenModel>,
+    sessions: Vec<SessionModel>,
+    login_attempts: Vec<LoginAttemptModel>,
+    signing_keys: Vec<SigningKeyModel>,
+}
+
+
+/// Backend sem persistência, para os testes e o desenvolvimento local.
+/// Respeita os mesmos índices únicos e vencimentos do MongoDB.
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+} impl MemoryStorage {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        // Um teste que falhou segurando a trava não invalida os dados.
+        self.tables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+
+#[async_trait]
+impl UserRepository for MemoryStorage {
+    async fn find_by_username(&self, username: &str) -> Result<Option<UserModel>, AppError> {
+        Ok(self.tables().users.iter().find(|user| user.username == username).cloned())
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<UserModel>, AppError> {
+        Ok(self.tables().users.iter().find(|user| &user._id == id).cloned())
+    }
+
+    async fn list(&self, is_active: Option<bool>, skip: u64, limit: i64) -> Result<Vec<UserModel>, AppError> {
+        let mut users: Vec<UserModel> = self.tables()
+            .users
+            .iter()
+            .filter(|user| is_active.is_none_or(|value| user.is_active == value))
+            .cloned()
+            .collect();
+
+        users.sort_by_key(|user| Reverse(user.created_at));
+
+        Ok(users.into_iter().skip(skip as usize).take(limit.max(0) as usize).collect())
+    }
+
+    async fn insert(&self, user: &UserModel) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if tables.users.iter().any(|value| value.username == user.username) {
+            return Err(AppError::Conflict("username"));
+        }
+
+        tables.users.push(user.clone());
+        Ok(())
+    }
+
+    async fn update(&self, id: &ObjectId, changes: &UserChanges) -> Result<Option<UserModel>, AppError> {
+        let mut tables = self.tables();
+
+        if let Some(username) = &changes.username {
+            if tables.users.iter().any(|user| &user.username == username && &user._id != id) {
+                return Err(AppError::Conflict("username"));
+            }
+        }
+
+        match tables.users.iter_mut().find(|user| &user._id == id) {
+            Some(user) => {
+                changes.apply(user);
+                Ok(Some(user.clone()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        let mut tables = self.tables();
+        let before = tables.users.len();
+
+        tables.users.retain(|user| &user._id != id);
+        Ok(tables.users.len() < before)
+    }
+
+    async fn set_password(&self, username: &str, password: &str) -> Result<(), AppError> {
+        if let Some(user) = self.tables().users.iter_mut().find(|user| user.username == username) {
+            user.password = password.to_string();
+        }
+
+        Ok(())
+    }
+
+    async fn record_login(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        match self.tables().users.iter_mut().find(|user| &user._id == id && user.is_active) {
+            Some(user) => {
+                user.last_login = Some(now);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+
+#[async_trait]
+impl GroupRepository for MemoryStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<GroupModel>, AppError> {
+        Ok(self.tables().groups.iter().find(|group| &group._id == id).cloned())
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<GroupModel>, AppError> {
+        Ok(self.tables().groups.iter().filter(|group| ids.contains(&group._id)).cloned().collect())
+    }
+
+    async fn list(&self) -> Result<Vec<GroupModel>, AppError> {
+        let mut groups = self.tables().groups.clone();
+
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(groups)
+    }
+
+    async fn insert(&self, group: &GroupModel) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if tables.groups.iter().any(|value| value.name == group.name) {
+            return Err(AppError::Conflict("group"));
+        }
+
+        tables.groups.push(group.clone());
+        Ok(())
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        Ok(self.tables().groups.iter().any(|group| &group._id == id))
+    }
+
+    async fn add_permission(&self, group: &ObjectId, permission: &PermissionModel) -> Result<(), AppError> {
+        if let Some(value) = self.tables().groups.iter_mut().find(|value| &value._id == group) {
+            if !value.permissions.iter().any(|perm| perm._id == permission._id) {
+                value.permissions.push(permission.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn remove_permission(&self, group: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        if let Some(value) = self.tables().groups.iter_mut().find(|value| &value._id == group) {
+            value.permissions.retain(|perm| &perm._id != permission);
+        }
+
+        Ok(())
+    }
+}
+
+
+#[async_trait]
+impl PermissionRepository for MemoryStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<PermissionModel>, AppError> {
+        Ok(self.tables().permissions.iter().find(|permission| &permission._id == id).cloned())
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<PermissionModel>, AppError> {
+        Ok(self.tables().permissions.iter().filter(|permission| ids.contains(&permission._id)).cloned().collect())
+    }
+
+    async fn list(&self) -> Result<Vec<PermissionModel>, AppError> {
+        let mut permissions = self.tables().permissions.clone();
+
+        permissions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(permissions)
+    }
+
+    async fn insert(&self, permission: &PermissionModel) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if tables.permissions.iter().any(|value| value.name == permission.name) {
+            return Err(AppError::Conflict("permission"));
+        }
+
+        tables.permissions.push(permission.clone());
+        Ok(())
+    }
+}
+
+
+#[async_trait]
+impl MicroServiceRepository for MemoryStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<MicroServiceModel>, AppError> {
+        Ok(self.tables().micro_services.iter().find(|micro_service| &micro_service._id == id).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<MicroServiceModel>, AppError> {
+        let mut micro_services = self.tables().micro_services.clone();
+
+        micro_services.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(micro_services)
+    }
+
+    async fn insert(&self, micro_service: &MicroServiceModel) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if tables.micro_services.iter().any(|value| value.name == micro_service.name) {
+            return Err(AppError::Conflict("micro service"));
+        }
+
+        tables.micro_services.push(micro_service.clone());
+        Ok(())
+    }
+
+    async fn update(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<Option<MicroServiceModel>, AppError> {
+        let mut tables = self.tables();
+
+        if tables.micro_services.iter().any(|value| value.name == name && &value._id != id) {
+            return Err(AppError::Conflict("micro service"));
+        }
+
+        match tables.micro_services.iter_mut().find(|value| &value._id == id) {
+            Some(micro_service) => {
+                micro_service.name = name.to_string();
+                micro_service.host = host.to_string();
+                micro_service.routes = routes.to_vec();
+                Ok(Some(micro_service.clone()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        let mut tables = self.tables();
+        let before = tables.micro_services.len();
+
+        tables.micro_services.retain(|micro_service| &micro_service._id != id);
+        Ok(tables.micro_services.len() < before)
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        Ok(self.tables().micro_services.iter().any(|micro_service| &micro_service._id == id))
+    }
+
+    async fn routes_taken(&self, except: Option<&ObjectId>, routes: &[String]) -> Result<bool, AppError> {
+        Ok(self.tables().micro_services.iter().any(|micro_service| {
+            except != Some(&micro_service._id)
+                && micro_service.routes.iter().any(|route| routes.contains(route))
+        }))
+    }
+}
+
+
+#[async_trait]
+impl RelationshipRepository for MemoryStorage {
+    async fn groups_of(&self, user: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.tables().users_groups.iter().filter(|relation| &relation.user == user).map(|relation| relation.group).collect())
+    }
+
+    async fn add_user(&self, group: &ObjectId, user: &ObjectId) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if !tables.users_groups.iter().any(|relation| &relation.user == user && &relation.group == group) {
+            tables.users_groups.push(UsersGroup { user: *user, group: *group });
+        }
+
+        Ok(())
+    }
+
+    async fn remove_user(&self, group: &ObjectId, user: &ObjectId) -> Result<bool, AppError> {
+        let mut tables = self.tables();
+        let before = tables.users_groups.len();
+
+        tables.users_groups.retain(|relation| !(&relation.user == user && &relation.group == group));
+        Ok(tables.users_groups.len() < before)
+    }
+
+    async fn remove_user_everywhere(&self, user: &ObjectId) -> Result<(), AppError> {
+        self.tables().users_groups.retain(|relation| &relation.user != user);
+        Ok(())
+    }
+
+    async fn permissions_of(&self, micro_service: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.tables()
+            .micro_services_permission
+            .iter()
+            .filter(|relation| &relation.micro_service == micro_service)
+            .map(|relation| relation.permission)
+            .collect())
+    }
+
+    async fn link(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        if tables.micro_services_permission.iter().any(|relation| &relation.micro_service == micro_service && &relation.permission == permission) {
+            return Err(AppError::Conflict("link"));
+        }
+
+        tables.micro_services_permission.push(MicroServicePermission {
+            micro_service: *micro_service,
+            permission: *permission,
+        });
+        Ok(())
+    }
+
+    async fn unlink(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<bool, AppError> {
+        let mut tables = self.tables();
+        let before = tables.micro_services_permission.len();
+
+        tables.micro_services_permission.retain(|relation| !(&relation.micro_service == micro_service && &relation.permission == permission));
+        Ok(tables.micro_services_permission.len() < before)
+    }
+
+    async fn unlink_everywhere(&self, micro_service: &ObjectId) -> Result<(), AppError> {
+        self.tables().micro_services_permission.retain(|relation| &relation.micro_service != micro_service);
+        Ok(())
+    }
+}
+
+
+#[async_trait]
+impl TokenRepository for MemoryStorage {
+    async fn insert_refresh(&self, token: &RefreshTokenModel) -> Result<(), AppError> {
+        self.tables().refresh_tokens.push(token.clone());
+        Ok(())
+    }
+
+    async fn find_refresh(&self, token_hash: &str) -> Result<Option<RefreshTokenModel>, AppError> {
+        Ok(self.tables().refresh_tokens.iter().find(|token| token.token_hash == token_hash).cloned())
+    }
+
+    async fn mark_rotated(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        match self.tables()
+            .refresh_tokens
+            .iter_mut()
+            .find(|token| &token._id == id && token.rotated_at.is_none() && !token.revoked) {
+                Some(token) => {
+                    token.rotated_at = Some(now);
+                    Ok(true)
+                },
+                None => Ok(false),
+            }
+    }
+
+    async fn revoke_family(&self, family: &ObjectId) -> Result<u64, AppError> {
+        let mut count = 0;
+
+        for token in self.tables().refresh_tokens.iter_mut().filter(|token| &token.family == family && !token.revoked) {
+            token.revoked = true;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    async fn revoke_user(&self, user: &ObjectId) -> Result<u64, AppError> {
+        let mut count = 0;
+
+        for token in self.tables().refresh_tokens.iter_mut().filter(|token| &token.user == user && !token.revoked) {
+            token.revoked = true;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    async fn insert_revoked(&self, token: &RevokedTokenModel) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        // Já revogado anteriormente.
+        if !tables.revoked_tokens.iter().any(|value| value.jti == token.jti) {
+            tables.revoked_tokens.push(token.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        Ok(self.tables().revoked_tokens.iter().any(|token| token.jti == jti))
+    }
+}
+
+
+#[async_trait]
+impl SessionRepository for MemoryStorage {
+    async fn insert(&self, session: &SessionModel) -> Result<(), AppError> {
+        self.tables().sessions.push(session.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<SessionModel>, AppError> {
+        let now = DateTime::now();
+
+        Ok(self.tables().sessions.iter().find(|session| &session._id == id && session.expires_at > now).cloned())
+    }
+
+    async fn list(&self, user: &ObjectId) -> Result<Vec<SessionModel>, AppError> {
+        let now = DateTime::now();
+        let mut sessions: Vec<SessionModel> = self.tables()
+            .sessions
+            .iter()
+            .filter(|session| &session.user == user && session.expires_at > now)
+            .cloned()
+            .collect();
+
+        sessions.sort_by_key(|session| Reverse(session.last_seen));
+        Ok(sessions)
+    }
+
+    async fn renew(&self, id: &ObjectId, jti: &str, token_expires_at: DateTime, now: DateTime, expires_at: DateTime) -> Result<bool, AppError> {
+        match self.tables().sessions.iter_mut().find(|session| &session._id == id && session.expires_at > now) {
+            Some(session) => {
+                session.jti = jti.to_string();
+                session.token_expires_at = token_expires_at;
+                session.last_seen = now;
+                session.expires_at = expires_at;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    async fn touch(&self, id: &ObjectId, now: DateTime) -> Result<(), AppError> {
+        if let Some(session) = self.tables().sessions.iter_mut().find(|session| &session._id == id) {
+            session.last_seen = now;
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<(), AppError> {
+        self.tables().sessions.retain(|session| &session._id != id);
+        Ok(())
+    }
+}
+
+
+#[async_trait]
+impl LoginAttemptRepository for MemoryStorage {
+    async fn find_blocked(&self, keys: &[String], now: DateTime) -> Result<Option<LoginAttemptModel>, AppError> {
+        Ok(self.tables()
+            .login_attempts
+            .iter()
+            .filter(|attempt| keys.contains(&attempt.key) && attempt.blocked_until > now)
+            .max_by_key(|attempt| attempt.blocked_until)
+            .cloned())
+    }
+
+    async fn record_failure(&self, key: &str, now: DateTime) -> Result<LoginAttemptModel, AppError> {
+        let mut tables = self.tables();
+
+        // Contador vencido equivale ao removido pelo índice TTL.
+        tables.login_attempts.retain(|attempt| attempt.key != key || attempt.expires_at > now);
+
+        match tables.login_attempts.iter_mut().find(|attempt| attempt.key == key) {
+            Some(attempt) => {
+                attempt.failures += 1;
+                attempt.last_failure = now;
+                Ok(attempt.clone())
+            },
+            None => {
+                let attempt = LoginAttemptModel {
+                    _id: ObjectId::new(),
+                    key: key.to_string(),
+                    failures: 1,
+                    last_failure: now,
+                    blocked_until: now,
+                    expires_at: now,
+                };
+
+                tables.login_attempts.push(attempt.clone());
+                Ok(attempt)
+            }
+        }
+    }
+
+    async fn block(&self, id: &ObjectId, blocked_until: DateTime, expires_at: DateTime) -> Result<(), AppError> {
+        if let Some(attempt) = self.tables().login_attempts.iter_mut().find(|attempt| &attempt._id == id) {
+            attempt.blocked_until = blocked_until;
+            attempt.expires_at = expires_at;
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool, AppError> {
+        let mut tables = self.tables();
+        let before = tables.login_attempts.len();
+
+        tables.login_attempts.retain(|attempt| attempt.key != key);
+        Ok(tables.login_attempts.len() < before)
+    }
+}
+
+
+#[async_trait]
+impl SigningKeyRepository for MemoryStorage {
+    async fn list(&self) -> Result<Vec<SigningKeyModel>, AppError> {
+        let mut keys = self.tables().signing_keys.clone();
+
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+
+    async fn find_by_kid(&self, kid: &str) -> Result<Option<SigningKeyModel>, AppError> {
+        Ok(self.tables().signing_keys.iter().find(|key| key.kid == kid).cloned())
+    }
+
+    async fn has_active(&self) -> Result<bool, AppError> {
+        Ok(self.tables().signing_keys.iter().any(|key| key.status == KeyStatus::Active))
+    }
+
+    async fn demote_active(&self, kid: &str, retire_at: DateTime) -> Result<u64, AppError> {
+        let mut count = 0;
+
+        for key in self.tables().signing_keys.iter_mut().filter(|key| key.status == KeyStatus::Active && key.kid != kid) {
+            key.status = KeyStatus::Verify;
+            key.retire_at = Some(retire_at);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    async fn retire_due(&self, now: DateTime) -> Result<u64, AppError> {
+        let mut count = 0;
+
+        for key in self.tables().signing_keys.iter_mut() {
+            if key.status == KeyStatus::Verify && key.retire_at.is_some_and(|value| value <= now) {
+                key.status = KeyStatus::Retired;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn upsert(&self, kid: &str, status: KeyStatus, retire_at: Option<DateTime>, now: DateTime) -> Result<(), AppError> {
+        let mut tables = self.tables();
+
+        match tables.signing_keys.iter_mut().find(|key| key.kid == kid) {
+            Some(key) => {
+                key.status = status;
+                key.retire_at = retire_at;
+            },
+            None => tables.signing_keys.push(SigningKeyModel {
+                _id: ObjectId::new(),
+                kid: kid.to_string(),
+                status,
+                created_at: now,
+                retire_at,
+            }),
+        };
+
+        Ok(())
+    }
+}
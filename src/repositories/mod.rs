@@ -0,0 +1,268 @@
+pub mod memory;
+pub mod mongo;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub mod sql;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::models::groups::GroupModel;
+use crate::models::keys::{KeyStatus, SigningKeyModel};
+use crate::models::micro_services::MicroServiceModel;
+use crate::models::permissions::PermissionModel;
+use crate::models::sessions::SessionModel;
+use crate::models::tokens::{LoginAttemptModel, RefreshTokenModel, RevokedTokenModel};
+use crate::models::users::{UserChanges, UserModel};
+
+
+/// Acesso aos usuários.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_username(&self, username: &str) -> Result<Option<UserModel>, AppError>;
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<UserModel>, AppError>;
+
+    /// Usuários dos mais recentes para os mais antigos.
+    async fn list(&self, is_active: Option<bool>, skip: u64, limit: i64) -> Result<Vec<UserModel>, AppError>;
+
+    /// Falha com `Conflict` se o username já existir.
+    async fn insert(&self, user: &UserModel) -> Result<(), AppError>;
+
+    /// Retorna o usuário já atualizado, se existir.
+    async fn update(&self, id: &ObjectId, changes: &UserChanges) -> Result<Option<UserModel>, AppError>;
+
+    /// Retorna falso se o usuário não existir.
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError>;
+
+    async fn set_password(&self, username: &str, password: &str) -> Result<(), AppError>;
+
+    /// Grava o último login apenas de contas ativas.
+    /// Retorna falso se a conta não existir ou estiver desativada.
+    async fn record_login(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError>;
+}
+
+
+/// Acesso aos grupos e às permissões embutidas neles.
+#[async_trait]
+pub trait GroupRepository: Send + Sync {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<GroupModel>, AppError>;
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<GroupModel>, AppError>;
+
+    /// Grupos em ordem alfabética.
+    async fn list(&self) -> Result<Vec<GroupModel>, AppError>;
+
+    /// Falha com `Conflict` se o nome já existir.
+    async fn insert(&self, group: &GroupModel) -> Result<(), AppError>;
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError>;
+
+    /// Ignora a permissão já presente no grupo.
+    async fn add_permission(&self, group: &ObjectId, permission: &PermissionModel) -> Result<(), AppError>;
+
+    async fn remove_permission(&self, group: &ObjectId, permission: &ObjectId) -> Result<(), AppError>;
+}
+
+
+/// Acesso às permissões.
+#[async_trait]
+pub trait PermissionRepository: Send + Sync {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<PermissionModel>, AppError>;
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<PermissionModel>, AppError>;
+
+    /// Permissões em ordem alfabética.
+    async fn list(&self) -> Result<Vec<PermissionModel>, AppError>;
+
+    /// Falha com `Conflict` se o nome já existir.
+    async fn insert(&self, permission: &PermissionModel) -> Result<(), AppError>;
+}
+
+
+/// Acesso ao registro dos micro serviços.
+#[async_trait]
+pub trait MicroServiceRepository: Send + Sync {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<MicroServiceModel>, AppError>;
+
+    /// Micro serviços em ordem alfabética.
+    async fn list(&self) -> Result<Vec<MicroServiceModel>, AppError>;
+
+    /// Falha com `Conflict` se o nome já existir.
+    async fn insert(&self, micro_service: &MicroServiceModel) -> Result<(), AppError>;
+
+    /// Retorna o cadastro já atualizado, se existir.
+    async fn update(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<Option<MicroServiceModel>, AppError>;
+
+    /// Retorna falso se o micro serviço não existir.
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError>;
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError>;
+
+    /// Valida se algum dos prefixos pertence a outro micro serviço.
+    async fn routes_taken(&self, except: Option<&ObjectId>, routes: &[String]) -> Result<bool, AppError>;
+}
+
+
+/// Acesso aos relacionamentos entre usuários e grupos
+/// e entre micro serviços e permissões.
+#[async_trait]
+pub trait RelationshipRepository: Send + Sync {
+    async fn groups_of(&self, user: &ObjectId) -> Result<Vec<ObjectId>, AppError>;
+
+    /// Ignora o relacionamento já existente.
+    async fn add_user(&self, group: &ObjectId, user: &ObjectId) -> Result<(), AppError>;
+
+    /// Retorna falso se o relacionamento não existir.
+    async fn remove_user(&self, group: &ObjectId, user: &ObjectId) -> Result<bool, AppError>;
+
+    /// Remove o usuário de todos os grupos.
+    async fn remove_user_everywhere(&self, user: &ObjectId) -> Result<(), AppError>;
+
+    async fn permissions_of(&self, micro_service: &ObjectId) -> Result<Vec<ObjectId>, AppError>;
+
+    /// Falha com `Conflict` se o relacionamento já existir.
+    async fn link(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<(), AppError>;
+
+    /// Retorna falso se o relacionamento não existir.
+    async fn unlink(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<bool, AppError>;
+
+    /// Remove todas as permissões do micro serviço.
+    async fn unlink_everywhere(&self, micro_service: &ObjectId) -> Result<(), AppError>;
+}
+
+
+/// Acesso aos tokens de renovação e à lista de tokens de acesso revogados.
+#[async_trait]
+pub trait TokenRepository: Send + Sync {
+    async fn insert_refresh(&self, token: &RefreshTokenModel) -> Result<(), AppError>;
+
+    async fn find_refresh(&self, token_hash: &str) -> Result<Option<RefreshTokenModel>, AppError>;
+
+    /// Marca o token como trocado, se ninguém o fez antes e ele não foi revogado.
+    /// Retorna falso se outra requisição chegou primeiro.
+    async fn mark_rotated(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError>;
+
+    /// Retorna quantos tokens foram revogados.
+    async fn revoke_family(&self, family: &ObjectId) -> Result<u64, AppError>;
+
+    /// Retorna quantos tokens foram revogados.
+    async fn revoke_user(&self, user: &ObjectId) -> Result<u64, AppError>;
+
+    /// Ignora o token já revogado.
+    async fn insert_revoked(&self, token: &RevokedTokenModel) -> Result<(), AppError>;
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError>;
+}
+
+
+/// Acesso às sessões dos usuários.
+/// Sessões vencidas são tratadas como inexistentes.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn insert(&self, session: &SessionModel) -> Result<(), AppError>;
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<SessionModel>, AppError>;
+
+    /// Sessões do usuário das mais recentes para as mais antigas.
+    async fn list(&self, user: &ObjectId) -> Result<Vec<SessionModel>, AppError>;
+
+    /// Associa o novo token de acesso à sessão.
+    /// Retorna falso se a sessão não existir mais.
+    async fn renew(&self, id: &ObjectId, jti: &str, token_expires_at: DateTime, now: DateTime, expires_at: DateTime) -> Result<bool, AppError>;
+
+    async fn touch(&self, id: &ObjectId, now: DateTime) -> Result<(), AppError>;
+
+    async fn delete(&self, id: &ObjectId) -> Result<(), AppError>;
+}
+
+
+/// Acesso aos contadores de falhas de login.
+#[async_trait]
+pub trait LoginAttemptRepository: Send + Sync {
+    /// Contador bloqueado há mais tempo entre as chaves, se houver.
+    async fn find_blocked(&self, keys: &[String], now: DateTime) -> Result<Option<LoginAttemptModel>, AppError>;
+
+    /// Soma uma falha, criando o contador se preciso.
+    async fn record_failure(&self, key: &str, now: DateTime) -> Result<LoginAttemptModel, AppError>;
+
+    async fn block(&self, id: &ObjectId, blocked_until: DateTime, expires_at: DateTime) -> Result<(), AppError>;
+
+    /// Retorna falso se não houver contador.
+    async fn delete(&self, key: &str) -> Result<bool, AppError>;
+}
+
+
+/// Acesso ao estado das chaves de assinatura.
+#[async_trait]
+pub trait SigningKeyRepository: Send + Sync {
+    /// Chaves das mais antigas para as mais novas.
+    async fn list(&self) -> Result<Vec<SigningKeyModel>, AppError>;
+
+    async fn find_by_kid(&self, kid: &str) -> Result<Option<SigningKeyModel>, AppError>;
+
+    async fn has_active(&self) -> Result<bool, AppError>;
+
+    /// Rebaixa as chaves ativas, exceto `kid`, a apenas validar até `retire_at`.
+    /// Retorna quantas foram rebaixadas.
+    async fn demote_active(&self, kid: &str, retire_at: DateTime) -> Result<u64, AppError>;
+
+    /// Aposenta as chaves de validação vencidas. Retorna quantas foram aposentadas.
+    async fn retire_due(&self, now: DateTime) -> Result<u64, AppError>;
+
+    /// Grava o estado da chave, registrando-a se necessário.
+    async fn upsert(&self, kid: &str, status: KeyStatus, retire_at: Option<DateTime>, now: DateTime) -> Result<(), AppError>;
+}
+
+
+/// Repositórios usados pelos serviços, todos do mesmo backend.
+/// O clone é barato: apenas as referências são copiadas.
+#[derive(Clone)]
+pub struct Storage {
+    pub users: Arc<dyn UserRepository>,
+    pub groups: Arc<dyn GroupRepository>,
+    pub permissions: Arc<dyn PermissionRepository>,
+    pub micro_services: Arc<dyn MicroServiceRepository>,
+    pub relationships: Arc<dyn RelationshipRepository>,
+    pub tokens: Arc<dyn TokenRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub login_attempts: Arc<dyn LoginAttemptRepository>,
+    pub signing_keys: Arc<dyn SigningKeyRepository>,
+} impl Storage {
+    /// Usa o mesmo backend para todos os repositórios.
+    pub fn new<B>(backend: B) -> Self
+    where
+        B: UserRepository
+            + GroupRepository
+            + PermissionRepository
+            + MicroServiceRepository
+            + RelationshipRepository
+            + TokenRepository
+            + SessionRepository
+            + LoginAttemptRepository
+            + SigningKeyRepository
+            + 'static,
+    {
+        let backend = Arc::new(backend);
+
+        Storage {
+            users: backend.clone(),
+            groups: backend.clone(),
+            permissions: backend.clone(),
+            micro_services: backend.clone(),
+            relationships: backend.clone(),
+            tokens: backend.clone(),
+            sessions: backend.clone(),
+            login_attempts: backend.clone(),
+            signing_keys: backend,
+        }
+    }
+
+    /// Backend em memória, sem persistência.
+    pub fn memory() -> Self {
+        Storage::new(memory::MemoryStorage::default())
+    }
+}
@@ -0,0 +1,1309 @@
+use core::panic;
+use std::time::Duration;
+
+use log::{debug, info, error};
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use futures_util::stream::TryStreamExt;
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+use mongodb::options::{ClientOptions, IndexOptions, ReturnDocument};
+use mongodb::{
+    Client,
+    Collection,
+    Cursor,
+    Database,
+    IndexModel,
+    bson::{doc, to_bson, DateTime, Document}
+};
+use serde::de::DeserializeOwned;
+
+use crate::errors::AppError;
+use crate::repositories::{
+    GroupRepository,
+    LoginAttemptRepository,
+    MicroServiceRepository,
+    PermissionRepository,
+    RelationshipRepository,
+    SessionRepository,
+    SigningKeyRepository,
+    TokenRepository,
+    UserRepository,
+};
+use crate::settings::Settings;
+use crate::models::{
+    users::{UserChanges, UserModel},
+    permissions::PermissionModel,
+    groups::GroupModel,
+    micro_services::MicroServiceModel,
+    relationship::{UsersGroup, MicroServicePermission},
+    tokens::{LoginAttemptModel, RefreshTokenModel, RevokedTokenModel},
+    keys::{KeyStatus, SigningKeyModel},
+    sessions::SessionModel,
+};
+
+
+/// Esturura com as coleções de dados a serem usadas no serviço.
+/// O clone é barato: todas as coleções compartilham o mesmo cliente.
+#[derive(Clone)]
+pub struct MongoService {
+    pub user_model: Collection<UserModel>,
+    pub permissions_model: Collection<PermissionModel>,
+    pub groups_model: Collection<GroupModel>,
+    pub micro_services_model: Collection<MicroServiceModel>,
+    pub users_groups: Collection<UsersGroup>,
+    pub micro_services_permission: Collection<MicroServicePermission>,
+    pub refresh_tokens: Collection<RefreshTokenModel>,
+    pub signing_keys: Collection<SigningKeyModel>,
+    pub revoked_tokens: Collection<RevokedTokenModel>,
+    pub sessions: Collection<SessionModel>,
+    pub login_attempts: Collection<LoginAttemptModel>,
+    db: Database,
+} impl MongoService {
+    pub async fn new(settings: &Settings) -> Self {
+        let mut options = match ClientOptions::parse(&settings.mongo_uri).await {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Invalid Mongo URI.");
+                panic!("Cause: {}", e);
+            }
+        };
+
+        // Pool de conexões conforme as configurações.
+        options.min_pool_size = Some(settings.mongo_min_pool_size);
+        options.max_pool_size = Some(settings.mongo_max_pool_size);
+        options.connect_timeout = Some(Duration::from_secs(settings.mongo_connect_timeout));
+
+        let client = Client::with_options(options).unwrap();
+        let db = client.database(&settings.mongo_db);
+
+        // Coleção de dados para os usuários.   
+        let users = "users";
+        // Coleção de dados para as permissões.
+        let permissions = "permissions";
+        // Coleção de dados para os grupos.
+        let groups = "groups";
+        // Coleção para armazenamento das rotas dos micro serviços.
+        let micro_services= "micro_services";
+        // Coleção para relacionamento de grupos e usuários.
+        let users_groups = "users_groups";
+        // Coleção para relacionamento de micro serviços e permissões.
+        let micro_service_permission = "micro_service_permission";
+        // Coleção para os tokens de renovação.
+        let refresh_tokens = "refresh_tokens";
+        // Coleção com o estado das chaves de assinatura.
+        let signing_keys = "signing_keys";
+        // Coleção com os tokens de acesso revogados.
+        let revoked_tokens = "revoked_tokens";
+        // Coleção com as sessões dos usuários por dispositivo.
+        let sessions = "sessions";
+        // Coleção com os contadores de falhas de login.
+        let login_attempts = "login_attempts";
+
+        let user_model: Collection<UserModel> = db.collection(users);
+        let permissions_model: Collection<PermissionModel> = db.collection(permissions);
+        let groups_model: Collection<GroupModel> = db.collection(groups);
+        let micro_services_model: Collection<MicroServiceModel> = db.collection(micro_services);
+        let users_groups: Collection<UsersGroup> = db.collection(users_groups);
+        let micro_services_permission: Collection<MicroServicePermission> = db.collection(micro_service_permission);
+        let refresh_tokens: Collection<RefreshTokenModel> = db.collection(refresh_tokens);
+        let signing_keys: Collection<SigningKeyModel> = db.collection(signing_keys);
+        let revoked_tokens: Collection<RevokedTokenModel> = db.collection(revoked_tokens);
+        let sessions: Collection<SessionModel> = db.collection(sessions);
+        let login_attempts: Collection<LoginAttemptModel> = db.collection(login_attempts);
+
+        MongoService{
+            user_model,
+            permissions_model,
+            groups_model,
+            micro_services_model,
+            users_groups,
+            micro_services_permission,
+            refresh_tokens,
+            signing_keys,
+            revoked_tokens,
+            sessions,
+            login_attempts,
+            db,
+        }
+    }
+
+    // Cria as coleções de dados e seus índices.
+    pub async fn migrate(&self) {
+        // Captura as os nomes das colections a serem migradas.
+        let collections: Vec<&str> = vec![
+            self.user_model.name(),
+            self.permissions_model.name(),
+            self.groups_model.name(),
+            self.micro_services_model.name(),
+            self.users_groups.name(),
+            self.micro_services_permission.name(),
+            self.refresh_tokens.name(),
+            self.signing_keys.name(),
+            self.revoked_tokens.name(),
+            self.sessions.name(),
+            self.login_attempts.name(),
+        ];
+
+        debug!("Verifying if collections already exists.");
+        // Captura os nomes das coleções existentes.
+        let collection_names = match self.db
+            .list_collection_names()
+            .await{
+                Ok(names) => names,
+                Err(e) => {
+                    error!("Can not verify collections.");
+                    panic!("Cause: {}", e);
+                }
+            };
+
+        for name in collections.iter() {
+            // Valida se a coleção já existe, se sim passa pra próxima.
+            if collection_names.iter().any(| coll | coll == name) {
+                debug!("Collection {} already exists!", name);
+                continue;
+            }
+
+            // Tenta criar a coleção de dados.
+            match self.db
+                .create_collection(*name)
+                .await{
+                    Ok(_) => info!("Collection {} has been created!", name),
+                    Err(e) => {
+                        error!("Can not create collection {}.", name);
+                        panic!("Cause: {}", e);
+                    }
+                };
+        }
+
+        // Opção para criar campos unique.
+        let unique_opt = IndexOptions::builder()
+            .unique(true)
+            .build();
+
+        // Cria os índices das coleções.
+        let user_username_idx = IndexModel::builder().keys(doc!{
+            "username": 1,
+        }).options(unique_opt.clone()).build();
+        // Coleção de usuários
+        let user_login_idx = IndexModel::builder().keys(doc!{
+            "username": 1,
+            "is_active": -1,
+        }).build();
+        let user_is_super_user_idx = IndexModel::builder().keys(doc!{
+            "username": 1,
+            "is_superuser": -1,
+        }).build();
+        let user_rest_filter_idx = IndexModel::builder().keys(doc!{
+            "is_active": 1,
+            "is_super_user": 1,
+            "created_at": -1,
+        }).build();
+
+        match self.user_model
+            .create_indexes(vec![
+                user_username_idx,
+                user_login_idx,
+                user_is_super_user_idx, 
+                user_rest_filter_idx,
+            ])
+            .await {
+                Ok(_) => info!("Created indexes for user collection!"),
+                Err(e) => error!("Can not create index for users collection.\nCause: {}", e),
+            };
+
+        // Coleção de permissões.
+        let permission_idx = IndexModel::builder().keys(doc!{
+            "name": 1
+        }).options(unique_opt.clone()).build();
+
+        match self.permissions_model
+            .create_index(permission_idx)
+            .await {
+                Ok(_) => info!("Created indexes for permission collection!"),
+                Err(e) => error!("Can not create index for permissions collection.\nCause: {}", e),
+            };
+
+        // Coleção de grupos.
+        let groups_name_idx = IndexModel::builder().keys(doc!{
+            "name": 1
+        }).options(unique_opt.clone()).build();
+        let groups_permission_idx = IndexModel::builder().keys(doc!{
+            "name": 1,
+            "permissions": 1,
+        }).build();
+
+        match self.groups_model
+            .create_indexes(vec![groups_name_idx, groups_permission_idx])
+            .await {
+                Ok(_) => info!("Created indexes for gorups collection!"),
+                Err(e) => error!("Can not create index for groups collection.\nCause: {}", e),
+            };
+
+        // Coleção de micro serviços.
+        let micro_services_idx = IndexModel::builder().keys(doc!{
+            "name": 1,
+        }).options(unique_opt.clone()).build();
+
+        let micro_services_routes_idx = IndexModel::builder().keys(doc!{
+            "routes": 1,
+        }).build();
+
+        match self.micro_services_model
+            .create_indexes(vec![micro_services_idx, micro_services_routes_idx])
+            .await {
+                Ok(_) => info!("Created indexes for micro_services collection!"),
+                Err(e) => error!("Can not create index for micro_services collection.\nCause: {}", e),
+            };
+
+        // Coleções de relacionamento.
+        let users_group_user_idx = IndexModel::builder().keys(doc!{
+            "user": 1,
+        }).build();
+        let user_groups_group_idx = IndexModel::builder().keys(doc!{
+            "group": 1,
+        }).build();
+
+        match self.users_groups
+            .create_indexes(vec![users_group_user_idx, user_groups_group_idx])
+            .await {
+                Ok(_) => info!("Create indexes for user_groups realationship"),
+                Err(e) => error!("Can not create indexes for user_groups relationship.\nCause: {}", e),
+            };
+
+        let micro_service_permission_mc_idx = IndexModel::builder().keys(doc!{
+            "micro_service": 1,
+        }).build();
+        let micro_service_permission_unq_idx = IndexModel::builder().keys(doc!{
+            "micro_service": 1,
+            "permission": 1,
+        }).options(unique_opt.clone()).build();
+
+        match self.micro_services_permission
+            .create_indexes(vec![micro_service_permission_mc_idx, micro_service_permission_unq_idx])
+            .await {
+                Ok(_) => info!("Created indexes for micro_services_permission relationship!"),
+                Err(e) => error!("Can not create indexes for micro_services_permission relationship.\nCause: {}", e),
+            };
+
+        // Coleção de tokens de renovação.
+        let refresh_token_hash_idx = IndexModel::builder().keys(doc!{
+            "token_hash": 1,
+        }).options(unique_opt.clone()).build();
+        let refresh_token_family_idx = IndexModel::builder().keys(doc!{
+            "family": 1,
+        }).build();
+        // Remove os tokens vencidos automaticamente.
+        let refresh_token_expires_idx = IndexModel::builder().keys(doc!{
+            "expires_at": 1,
+        }).options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build()
+        ).build();
+
+        match self.refresh_tokens
+            .create_indexes(vec![
+                refresh_token_hash_idx,
+                refresh_token_family_idx,
+                refresh_token_expires_idx,
+            ])
+            .await {
+                Ok(_) => info!("Created indexes for refresh_tokens collection!"),
+                Err(e) => error!("Can not create indexes for refresh_tokens collection.\nCause: {}", e),
+            };
+
+        // Coleção de chaves de assinatura.
+        let signing_keys_kid_idx = IndexModel::builder().keys(doc!{
+            "kid": 1,
+        }).options(unique_opt.clone()).build();
+        let signing_keys_status_idx = IndexModel::builder().keys(doc!{
+            "status": 1,
+            "retire_at": 1,
+        }).build();
+
+        match self.signing_keys
+            .create_indexes(vec![signing_keys_kid_idx, signing_keys_status_idx])
+            .await {
+                Ok(_) => info!("Created indexes for signing_keys collection!"),
+                Err(e) => error!("Can not create indexes for signing_keys collection.\nCause: {}", e),
+            };
+
+        // Coleção de tokens revogados.
+        let revoked_tokens_jti_idx = IndexModel::builder().keys(doc!{
+            "jti": 1,
+        }).options(unique_opt.clone()).build();
+        // Remove os registros quando o token venceria.
+        let revoked_tokens_expires_idx = IndexModel::builder().keys(doc!{
+            "expires_at": 1,
+        }).options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build()
+        ).build();
+
+        match self.revoked_tokens
+            .create_indexes(vec![revoked_tokens_jti_idx, revoked_tokens_expires_idx])
+            .await {
+                Ok(_) => info!("Created indexes for revoked_tokens collection!"),
+                Err(e) => error!("Can not create indexes for revoked_tokens collection.\nCause: {}", e),
+            };
+
+        // Coleção de sessões.
+        let sessions_user_idx = IndexModel::builder().keys(doc!{
+            "user": 1,
+            "last_seen": -1,
+        }).build();
+        // Remove as sessões junto com o token de renovação.
+        let sessions_expires_idx = IndexModel::builder().keys(doc!{
+            "expires_at": 1,
+        }).options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build()
+        ).build();
+
+        match self.sessions
+            .create_indexes(vec![sessions_user_idx, sessions_expires_idx])
+            .await {
+                Ok(_) => info!("Created indexes for sessions collection!"),
+                Err(e) => error!("Can not create indexes for sessions collection.\nCause: {}", e),
+            };
+
+        // Coleção de falhas de login.
+        let login_attempts_key_idx = IndexModel::builder().keys(doc!{
+            "key": 1,
+        }).options(unique_opt.clone()).build();
+        // Descarta os contadores sem falhas recentes.
+        let login_attempts_expires_idx = IndexModel::builder().keys(doc!{
+            "expires_at": 1,
+        }).options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build()
+        ).build();
+
+        match self.login_attempts
+            .create_indexes(vec![login_attempts_key_idx, login_attempts_expires_idx])
+            .await {
+                Ok(_) => info!("Created indexes for login_attempts collection!"),
+                Err(e) => error!("Can not create indexes for login_attempts collection.\nCause: {}", e),
+            };
+    }
+}
+
+
+/// Valida se o erro foi causado por violação de índice único.
+pub fn is_duplicate_key(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == 11000,
+        ErrorKind::Command(e) => e.code == 11000,
+        _ => false,
+    }
+}
+
+
+/// Lê todos os documentos de uma consulta.
+async fn collect<T>(cursor: Result<Cursor<T>, Error>, collection: &str) -> Result<Vec<T>, AppError>
+where
+    T: DeserializeOwned + Unpin + Send + Sync,
+{
+    let cursor = match cursor {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Can not filter {}, cause {}", collection, e);
+            return Err(AppError::Database);
+        }
+    };
+
+    match cursor.try_collect().await {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            error!("Can not read {}, cause {}", collection, e);
+            Err(AppError::Database)
+        }
+    }
+}
+
+
+/// Monta o `$set` apenas com os campos alterados.
+fn user_changes(changes: &UserChanges) -> Document {
+    let mut fields = Document::new();
+
+    if let Some(value) = &changes.username {
+        fields.insert("username", value);
+    }
+    if let Some(value) = &changes.email {
+        fields.insert("email", value);
+    }
+    if let Some(value) = &changes.password {
+        fields.insert("password", value);
+    }
+    if let Some(value) = &changes.first_name {
+        fields.insert("first_name", value);
+    }
+    if let Some(value) = &changes.last_name {
+        fields.insert("last_name", value);
+    }
+    if let Some(value) = changes.is_active {
+        fields.insert("is_active", value);
+    }
+    if let Some(value) = changes.is_superuser {
+        fields.insert("is_superuser", value);
+    }
+
+    fields
+}
+
+
+#[async_trait]
+impl UserRepository for MongoService {
+    async fn find_by_username(&self, username: &str) -> Result<Option<UserModel>, AppError> {
+        match self.user_model
+            .find_one(doc!{"username": username})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in users, cause {}", username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<UserModel>, AppError> {
+        match self.user_model
+            .find_one(doc!{"_id": id})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in users, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self, is_active: Option<bool>, skip: u64, limit: i64) -> Result<Vec<UserModel>, AppError> {
+        let mut filter = Document::new();
+
+        if let Some(value) = is_active {
+            filter.insert("is_active", value);
+        }
+
+        let cursor = self.user_model
+            .find(filter)
+            .sort(doc!{"created_at": -1})
+            .skip(skip)
+            .limit(limit)
+            .await;
+
+        collect(cursor, "users").await
+    }
+
+    async fn insert(&self, user: &UserModel) -> Result<(), AppError> {
+        match self.user_model
+            .insert_one(user)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("username")),
+                Err(e) => {
+                    error!("Can not create user {}, cause {}", &user.username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn update(&self, id: &ObjectId, changes: &UserChanges) -> Result<Option<UserModel>, AppError> {
+        let update = doc!{
+            "$set": user_changes(changes),
+        };
+
+        match self.user_model
+            .find_one_and_update(doc!{"_id": id}, update)
+            .return_document(ReturnDocument::After)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("username")),
+                Err(e) => {
+                    error!("Can not update user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match self.user_model
+            .delete_one(doc!{"_id": id})
+            .await {
+                Ok(result) => Ok(result.deleted_count == 1),
+                Err(e) => {
+                    error!("Can not delete user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn set_password(&self, username: &str, password: &str) -> Result<(), AppError> {
+        let update = doc!{
+            "$set": {
+                "password": password,
+            }
+        };
+
+        match self.user_model
+            .update_one(doc!{"username": username}, update)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not update password of user {}, cause {}", username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn record_login(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        let query = doc!{
+            "_id": id,
+            "is_active": true,
+        };
+        let update = doc!{
+            "$set": {
+                "last_login": now,
+            }
+        };
+
+        match self.user_model
+            .update_one(query, update)
+            .await {
+                Ok(result) => Ok(result.matched_count == 1),
+                Err(e) => {
+                    error!("Can not record login of user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl GroupRepository for MongoService {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<GroupModel>, AppError> {
+        match self.groups_model
+            .find_one(doc!{"_id": id})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in groups, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<GroupModel>, AppError> {
+        let cursor = self.groups_model
+            .find(doc!{"_id": {"$in": ids}})
+            .await;
+
+        collect(cursor, "groups").await
+    }
+
+    async fn list(&self) -> Result<Vec<GroupModel>, AppError> {
+        let cursor = self.groups_model
+            .find(doc!{})
+            .sort(doc!{"name": 1})
+            .await;
+
+        collect(cursor, "groups").await
+    }
+
+    async fn insert(&self, group: &GroupModel) -> Result<(), AppError> {
+        match self.groups_model
+            .insert_one(group)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("group")),
+                Err(e) => {
+                    error!("Can not create group {}, cause {}", &group.name, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match self.groups_model
+            .count_documents(doc!{"_id": id})
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not filter {} in groups, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn add_permission(&self, group: &ObjectId, permission: &PermissionModel) -> Result<(), AppError> {
+        let value = match to_bson(permission) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Can not serialize permission {}, cause {}", &permission._id, e);
+                return Err(AppError::Internal("can not serialize permission"));
+            }
+        };
+        let query = doc!{
+            "_id": group,
+            "permissions._id": {"$ne": permission._id},
+        };
+        let update = doc!{
+            "$push": {
+                "permissions": value,
+            }
+        };
+
+        match self.groups_model
+            .update_one(query, update)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not add permission {} to group {}, cause {}", &permission.name, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_permission(&self, group: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        let update = doc!{
+            "$pull": {
+                "permissions": {"_id": permission},
+            }
+        };
+
+        match self.groups_model
+            .update_one(doc!{"_id": group}, update)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not remove permission {} from group {}, cause {}", permission, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl PermissionRepository for MongoService {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<PermissionModel>, AppError> {
+        match self.permissions_model
+            .find_one(doc!{"_id": id})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in permissions, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<PermissionModel>, AppError> {
+        let cursor = self.permissions_model
+            .find(doc!{"_id": {"$in": ids}})
+            .await;
+
+        collect(cursor, "permissions").await
+    }
+
+    async fn list(&self) -> Result<Vec<PermissionModel>, AppError> {
+        let cursor = self.permissions_model
+            .find(doc!{})
+            .sort(doc!{"name": 1})
+            .await;
+
+        collect(cursor, "permissions").await
+    }
+
+    async fn insert(&self, permission: &PermissionModel) -> Result<(), AppError> {
+        match self.permissions_model
+            .insert_one(permission)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("permission")),
+                Err(e) => {
+                    error!("Can not create permission {}, cause {}", &permission.name, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl MicroServiceRepository for MongoService {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<MicroServiceModel>, AppError> {
+        match self.micro_services_model
+            .find_one(doc!{"_id": id})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in micro services, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self) -> Result<Vec<MicroServiceModel>, AppError> {
+        let cursor = self.micro_services_model
+            .find(doc!{})
+            .sort(doc!{"name": 1})
+            .await;
+
+        collect(cursor, "micro services").await
+    }
+
+    async fn insert(&self, micro_service: &MicroServiceModel) -> Result<(), AppError> {
+        match self.micro_services_model
+            .insert_one(micro_service)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("micro service")),
+                Err(e) => {
+                    error!("Can not register micro service {}, cause {}", &micro_service.name, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn update(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<Option<MicroServiceModel>, AppError> {
+        let update = doc!{
+            "$set": {
+                "name": name,
+                "host": host,
+                "routes": routes,
+            }
+        };
+
+        match self.micro_services_model
+            .find_one_and_update(doc!{"_id": id}, update)
+            .return_document(ReturnDocument::After)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("micro service")),
+                Err(e) => {
+                    error!("Can not update micro service {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match self.micro_services_model
+            .delete_one(doc!{"_id": id})
+            .await {
+                Ok(result) => Ok(result.deleted_count == 1),
+                Err(e) => {
+                    error!("Can not deregister micro service {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match self.micro_services_model
+            .count_documents(doc!{"_id": id})
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not filter {} in micro services, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn routes_taken(&self, except: Option<&ObjectId>, routes: &[String]) -> Result<bool, AppError> {
+        let mut query = doc!{
+            "routes": {"$in": routes},
+        };
+
+        if let Some(value) = except {
+            query.insert("_id", doc!{"$ne": value});
+        }
+
+        match self.micro_services_model
+            .count_documents(query)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not verify routes of micro services, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl RelationshipRepository for MongoService {
+    async fn groups_of(&self, user: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        let cursor = self.users_groups
+            .find(doc!{"user": user})
+            .await;
+        let relations = collect(cursor, "users groups").await?;
+
+        Ok(relations.iter().map(|relation| relation.group).collect())
+    }
+
+    async fn add_user(&self, group: &ObjectId, user: &ObjectId) -> Result<(), AppError> {
+        let relation = doc!{
+            "user": user,
+            "group": group,
+        };
+
+        match self.users_groups
+            .update_one(relation.clone(), doc!{"$setOnInsert": relation})
+            .upsert(true)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not add user {} to group {}, cause {}", user, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_user(&self, group: &ObjectId, user: &ObjectId) -> Result<bool, AppError> {
+        let query = doc!{
+            "user": user,
+            "group": group,
+        };
+
+        match self.users_groups
+            .delete_many(query)
+            .await {
+                Ok(result) => Ok(result.deleted_count > 0),
+                Err(e) => {
+                    error!("Can not remove user {} from group {}, cause {}", user, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_user_everywhere(&self, user: &ObjectId) -> Result<(), AppError> {
+        match self.users_groups
+            .delete_many(doc!{"user": user})
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete groups relationship of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn permissions_of(&self, micro_service: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        let cursor = self.micro_services_permission
+            .find(doc!{"micro_service": micro_service})
+            .await;
+        let relations = collect(cursor, "micro services permissions").await?;
+
+        Ok(relations.iter().map(|relation| relation.permission).collect())
+    }
+
+    async fn link(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        let relation = MicroServicePermission {
+            micro_service: *micro_service,
+            permission: *permission,
+        };
+
+        match self.micro_services_permission
+            .insert_one(&relation)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_duplicate_key(&e) => Err(AppError::Conflict("link")),
+                Err(e) => {
+                    error!("Can not link permission {} to micro service {}, cause {}", permission, micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn unlink(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<bool, AppError> {
+        let query = doc!{
+            "micro_service": micro_service,
+            "permission": permission,
+        };
+
+        match self.micro_services_permission
+            .delete_one(query)
+            .await {
+                Ok(result) => Ok(result.deleted_count == 1),
+                Err(e) => {
+                    error!("Can not unlink permission {} from micro service {}, cause {}", permission, micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn unlink_everywhere(&self, micro_service: &ObjectId) -> Result<(), AppError> {
+        match self.micro_services_permission
+            .delete_many(doc!{"micro_service": micro_service})
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete permissions relationship of micro service {}, cause {}", micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl TokenRepository for MongoService {
+    async fn insert_refresh(&self, token: &RefreshTokenModel) -> Result<(), AppError> {
+        match self.refresh_tokens
+            .insert_one(token)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not store refresh token for user {}, cause {}", token.user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_refresh(&self, token_hash: &str) -> Result<Option<RefreshTokenModel>, AppError> {
+        match self.refresh_tokens
+            .find_one(doc!{"token_hash": token_hash})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter refresh token, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn mark_rotated(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        let query = doc!{
+            "_id": id,
+            "rotated_at": null,
+            "revoked": false,
+        };
+        let update = doc!{
+            "$set": {
+                "rotated_at": now,
+            }
+        };
+
+        match self.refresh_tokens
+            .update_one(query, update)
+            .await {
+                Ok(result) => Ok(result.modified_count == 1),
+                Err(e) => {
+                    error!("Can not rotate refresh token, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn revoke_family(&self, family: &ObjectId) -> Result<u64, AppError> {
+        match self.refresh_tokens
+            .update_many(doc!{"family": family}, doc!{"$set": {"revoked": true}})
+            .await {
+                Ok(result) => Ok(result.modified_count),
+                Err(e) => {
+                    error!("Can not revoke refresh token family {}, cause {}", family, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn revoke_user(&self, user: &ObjectId) -> Result<u64, AppError> {
+        let query = doc!{
+            "user": user,
+            "revoked": false,
+        };
+
+        match self.refresh_tokens
+            .update_many(query, doc!{"$set": {"revoked": true}})
+            .await {
+                Ok(result) => Ok(result.modified_count),
+                Err(e) => {
+                    error!("Can not revoke refresh tokens of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn insert_revoked(&self, token: &RevokedTokenModel) -> Result<(), AppError> {
+        match self.revoked_tokens
+            .insert_one(token)
+            .await {
+                Ok(_) => Ok(()),
+                // Já revogado anteriormente.
+                Err(e) if is_duplicate_key(&e) => Ok(()),
+                Err(e) => {
+                    error!("Can not revoke access token {}, cause {}", &token.jti, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        match self.revoked_tokens
+            .count_documents(doc!{"jti": jti})
+            .limit(1)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not verify revoked token {}, cause {}", jti, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl SessionRepository for MongoService {
+    async fn insert(&self, session: &SessionModel) -> Result<(), AppError> {
+        match self.sessions
+            .insert_one(session)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not store session for user {}, cause {}", session.user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<SessionModel>, AppError> {
+        match self.sessions
+            .find_one(doc!{"_id": id, "expires_at": {"$gt": DateTime::now()}})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter session {}, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self, user: &ObjectId) -> Result<Vec<SessionModel>, AppError> {
+        let cursor = self.sessions
+            .find(doc!{"user": user, "expires_at": {"$gt": DateTime::now()}})
+            .sort(doc!{"last_seen": -1})
+            .await;
+
+        collect(cursor, "sessions").await
+    }
+
+    async fn renew(&self, id: &ObjectId, jti: &str, token_expires_at: DateTime, now: DateTime, expires_at: DateTime) -> Result<bool, AppError> {
+        let update = doc!{
+            "$set": {
+                "jti": jti,
+                "token_expires_at": token_expires_at,
+                "last_seen": now,
+                "expires_at": expires_at,
+            }
+        };
+
+        match self.sessions
+            .update_one(doc!{"_id": id, "expires_at": {"$gt": now}}, update)
+            .await {
+                Ok(result) => Ok(result.matched_count == 1),
+                Err(e) => {
+                    error!("Can not renew session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn touch(&self, id: &ObjectId, now: DateTime) -> Result<(), AppError> {
+        match self.sessions
+            .update_one(doc!{"_id": id}, doc!{"$set": {"last_seen": now}})
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not touch session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<(), AppError> {
+        match self.sessions
+            .delete_one(doc!{"_id": id})
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl LoginAttemptRepository for MongoService {
+    async fn find_blocked(&self, keys: &[String], now: DateTime) -> Result<Option<LoginAttemptModel>, AppError> {
+        let query = doc!{
+            "key": {"$in": keys},
+            "blocked_until": {"$gt": now},
+        };
+
+        match self.login_attempts
+            .find_one(query)
+            .sort(doc!{"blocked_until": -1})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not verify login attempts, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn record_failure(&self, key: &str, now: DateTime) -> Result<LoginAttemptModel, AppError> {
+        let update = doc!{
+            "$inc": {"failures": 1},
+            "$set": {"last_failure": now},
+            "$setOnInsert": {"blocked_until": now, "expires_at": now},
+        };
+
+        match self.login_attempts
+            .find_one_and_update(doc!{"key": key}, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await {
+                Ok(Some(value)) => Ok(value),
+                Ok(None) => {
+                    error!("Can not record login failure of {}, upsert returned nothing.", key);
+                    Err(AppError::Database)
+                },
+                Err(e) => {
+                    error!("Can not record login failure of {}, cause {}", key, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn block(&self, id: &ObjectId, blocked_until: DateTime, expires_at: DateTime) -> Result<(), AppError> {
+        let update = doc!{
+            "$set": {
+                "blocked_until": blocked_until,
+                "expires_at": expires_at,
+            }
+        };
+
+        match self.login_attempts
+            .update_one(doc!{"_id": id}, update)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not block login attempts {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool, AppError> {
+        match self.login_attempts
+            .delete_one(doc!{"key": key})
+            .await {
+                Ok(result) => Ok(result.deleted_count > 0),
+                Err(e) => {
+                    error!("Can not reset login failures of {}, cause {}", key, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl SigningKeyRepository for MongoService {
+    async fn list(&self) -> Result<Vec<SigningKeyModel>, AppError> {
+        let cursor = self.signing_keys
+            .find(doc!{})
+            .sort(doc!{"created_at": 1})
+            .await;
+
+        collect(cursor, "signing keys").await
+    }
+
+    async fn find_by_kid(&self, kid: &str) -> Result<Option<SigningKeyModel>, AppError> {
+        match self.signing_keys
+            .find_one(doc!{"kid": kid})
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter signing key {}, cause {}", kid, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn has_active(&self) -> Result<bool, AppError> {
+        match self.signing_keys
+            .count_documents(doc!{"status": "active"})
+            .limit(1)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not count active signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn demote_active(&self, kid: &str, retire_at: DateTime) -> Result<u64, AppError> {
+        match self.signing_keys
+            .update_many(
+                doc!{"status": "active", "kid": {"$ne": kid}},
+                doc!{"$set": {"status": "verify", "retire_at": retire_at}},
+            )
+            .await {
+                Ok(result) => Ok(result.modified_count),
+                Err(e) => {
+                    error!("Can not demote signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn retire_due(&self, now: DateTime) -> Result<u64, AppError> {
+        match self.signing_keys
+            .update_many(
+                doc!{"status": "verify", "retire_at": {"$lte": now}},
+                doc!{"$set": {"status": "retired"}},
+            )
+            .await {
+                Ok(result) => Ok(result.modified_count),
+                Err(e) => {
+                    error!("Can not retire signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn upsert(&self, kid: &str, status: KeyStatus, retire_at: Option<DateTime>, now: DateTime) -> Result<(), AppError> {
+        let state = doc!{
+            "status": status.to_string(),
+            "retire_at": retire_at,
+        };
+
+        match self.signing_keys
+            .update_one(
+                doc!{"kid": kid},
+                doc!{"$set": state, "$setOnInsert": {"created_at": now}},
+            )
+            .upsert(true)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not write signing key {}, cause {}", kid, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
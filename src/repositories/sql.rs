@@ -0,0 +1,1584 @@
+use core::panic;
+
+use log::{debug, info, warn, error};
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{AnyConnection, AnyPool, Row};
+
+use crate::errors::AppError;
+use crate::repositories::{
+    GroupRepository,
+    LoginAttemptRepository,
+    MicroServiceRepository,
+    PermissionRepository,
+    RelationshipRepository,
+    SessionRepository,
+    SigningKeyRepository,
+    TokenRepository,
+    UserRepository,
+};
+use crate::models::{
+    users::{UserChanges, UserModel},
+    permissions::PermissionModel,
+    groups::{Actions, GroupModel},
+    micro_services::MicroServiceModel,
+    tokens::{LoginAttemptModel, RefreshTokenModel, RevokedTokenModel},
+    keys::{KeyStatus, SigningKeyModel},
+    sessions::SessionModel,
+};
+
+
+/// Esquema das tabelas, válido no PostgreSQL e no SQLite.
+/// Espelha as coleções e os índices únicos criados no MongoDB;
+/// os IDs continuam sendo ObjectId, gravados em hexadecimal,
+/// as datas em milissegundos e os booleanos como 0 ou 1,
+/// já que o driver genérico do sqlx não lê colunas BOOLEAN do SQLite.
+/// Todos os comandos são idempotentes e rodam a cada subida.
+const MIGRATIONS: &[&str] = &[
+    // Usuários.
+    "CREATE TABLE IF NOT EXISTS users (
+        id TEXT PRIMARY KEY,
+        username TEXT NOT NULL,
+        email TEXT NOT NULL,
+        password TEXT NOT NULL,
+        first_name TEXT NOT NULL,
+        last_name TEXT NOT NULL,
+        is_active SMALLINT NOT NULL,
+        is_superuser SMALLINT NOT NULL,
+        created_at BIGINT NOT NULL,
+        last_login BIGINT
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS users_username_idx ON users (username)",
+    "CREATE INDEX IF NOT EXISTS users_filter_idx ON users (is_active, is_superuser, created_at)",
+    // Permissões.
+    "CREATE TABLE IF NOT EXISTS permissions (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at BIGINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS permissions_name_idx ON permissions (name)",
+    // Grupos e as permissões que no MongoDB ficam embutidas no grupo.
+    "CREATE TABLE IF NOT EXISTS \"groups\" (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        can_read SMALLINT NOT NULL,
+        can_write SMALLINT NOT NULL,
+        can_delete SMALLINT NOT NULL,
+        created_at BIGINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS groups_name_idx ON \"groups\" (name)",
+    "CREATE TABLE IF NOT EXISTS group_permissions (
+        group_id TEXT NOT NULL,
+        permission_id TEXT NOT NULL,
+        PRIMARY KEY (group_id, permission_id)
+    )",
+    // Micro serviços e seus prefixos de rota, na ordem cadastrada.
+    "CREATE TABLE IF NOT EXISTS micro_services (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        host TEXT NOT NULL,
+        created_at BIGINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS micro_services_name_idx ON micro_services (name)",
+    "CREATE TABLE IF NOT EXISTS micro_service_routes (
+        micro_service_id TEXT NOT NULL,
+        position BIGINT NOT NULL,
+        route TEXT NOT NULL,
+        PRIMARY KEY (micro_service_id, position)
+    )",
+    "CREATE INDEX IF NOT EXISTS micro_service_routes_route_idx ON micro_service_routes (route)",
+    // Relacionamentos.
+    "CREATE TABLE IF NOT EXISTS users_groups (
+        user_id TEXT NOT NULL,
+        group_id TEXT NOT NULL,
+        PRIMARY KEY (user_id, group_id)
+    )",
+    "CREATE INDEX IF NOT EXISTS users_groups_group_idx ON users_groups (group_id)",
+    "CREATE TABLE IF NOT EXISTS micro_service_permission (
+        micro_service_id TEXT NOT NULL,
+        permission_id TEXT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS micro_service_permission_unq_idx ON micro_service_permission (micro_service_id, permission_id)",
+    // Tokens de renovação.
+    "CREATE TABLE IF NOT EXISTS refresh_tokens (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        family TEXT NOT NULL,
+        token_hash TEXT NOT NULL,
+        created_at BIGINT NOT NULL,
+        expires_at BIGINT NOT NULL,
+        rotated_at BIGINT,
+        revoked SMALLINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS refresh_tokens_hash_idx ON refresh_tokens (token_hash)",
+    "CREATE INDEX IF NOT EXISTS refresh_tokens_family_idx ON refresh_tokens (family)",
+    "CREATE INDEX IF NOT EXISTS refresh_tokens_user_idx ON refresh_tokens (user_id)",
+    "CREATE INDEX IF NOT EXISTS refresh_tokens_expires_idx ON refresh_tokens (expires_at)",
+    // Chaves de assinatura.
+    "CREATE TABLE IF NOT EXISTS signing_keys (
+        id TEXT PRIMARY KEY,
+        kid TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at BIGINT NOT NULL,
+        retire_at BIGINT
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS signing_keys_kid_idx ON signing_keys (kid)",
+    "CREATE INDEX IF NOT EXISTS signing_keys_status_idx ON signing_keys (status, retire_at)",
+    // Tokens de acesso revogados.
+    "CREATE TABLE IF NOT EXISTS revoked_tokens (
+        id TEXT PRIMARY KEY,
+        jti TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        revoked_at BIGINT NOT NULL,
+        expires_at BIGINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS revoked_tokens_jti_idx ON revoked_tokens (jti)",
+    "CREATE INDEX IF NOT EXISTS revoked_tokens_expires_idx ON revoked_tokens (expires_at)",
+    // Sessões.
+    "CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        user_agent TEXT,
+        ip TEXT,
+        jti TEXT NOT NULL,
+        token_expires_at BIGINT NOT NULL,
+        created_at BIGINT NOT NULL,
+        last_seen BIGINT NOT NULL,
+        expires_at BIGINT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS sessions_user_idx ON sessions (user_id, last_seen)",
+    "CREATE INDEX IF NOT EXISTS sessions_expires_idx ON sessions (expires_at)",
+    // Falhas de login.
+    "CREATE TABLE IF NOT EXISTS login_attempts (
+        id TEXT PRIMARY KEY,
+        key TEXT NOT NULL,
+        failures BIGINT NOT NULL,
+        last_failure BIGINT NOT NULL,
+        blocked_until BIGINT NOT NULL,
+        expires_at BIGINT NOT NULL
+    )",
+    "CREATE UNIQUE INDEX IF NOT EXISTS login_attempts_key_idx ON login_attempts (key)",
+    "CREATE INDEX IF NOT EXISTS login_attempts_expires_idx ON login_attempts (expires_at)",
+];
+
+const USER_COLUMNS: &str = "id, username, email, password, first_name, last_name, is_active, is_superuser, created_at, last_login";
+const GROUP_COLUMNS: &str = "id, name, can_read, can_write, can_delete, created_at";
+const PERMISSION_COLUMNS: &str = "id, name, created_at";
+const MICRO_SERVICE_COLUMNS: &str = "id, name, host, created_at";
+const REFRESH_TOKEN_COLUMNS: &str = "id, user_id, family, token_hash, created_at, expires_at, rotated_at, revoked";
+const SESSION_COLUMNS: &str = "id, user_id, user_agent, ip, jti, token_expires_at, created_at, last_seen, expires_at";
+const LOGIN_ATTEMPT_COLUMNS: &str = "id, key, failures, last_failure, blocked_until, expires_at";
+const SIGNING_KEY_COLUMNS: &str = "id, kid, status, created_at, retire_at";
+
+
+/// Backend relacional, PostgreSQL ou SQLite conforme a URL.
+/// O clone é barato: o pool de conexões é compartilhado.
+#[derive(Clone)]
+pub struct SqlStorage {
+    pool: AnyPool,
+} impl SqlStorage {
+    pub async fn new(url: &str, max_connections: u32) -> Self {
+        // Habilita os drivers compilados por feature.
+        sqlx::any::install_default_drivers();
+
+        match AnyPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await {
+                Ok(pool) => SqlStorage { pool },
+                Err(e) => {
+                    error!("Can not connect to the SQL database.");
+                    panic!("Cause: {}", e);
+                }
+            }
+    }
+
+    /// Cria as tabelas e seus índices.
+    pub async fn migrate(&self) {
+        debug!("Applying SQL schema.");
+
+        for statement in MIGRATIONS.iter() {
+            if let Err(e) = sqlx::query(statement).execute(&self.pool).await {
+                error!("Can not apply migration:\n{}", statement);
+                panic!("Cause: {}", e);
+            }
+        }
+
+        info!("SQL schema is up to date!");
+    }
+
+    /// Remove os registros vencidos, papel do índice TTL no MongoDB.
+    async fn purge(&self, table: &str, now: DateTime) {
+        let query = format!("DELETE FROM {} WHERE expires_at <= $1", table);
+
+        match sqlx::query(&query)
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(result) if result.rows_affected() > 0 => debug!("Purged {} expired rows from {}.", result.rows_affected(), table),
+                Ok(_) => (),
+                Err(e) => warn!("Can not purge {}, cause {}", table, e),
+            }
+    }
+
+    /// Grupos que satisfazem `condition`, com suas permissões.
+    /// Os IDs são ligados, em ordem, aos parâmetros da condição.
+    async fn select_groups(&self, condition: &str, ids: &[ObjectId]) -> Result<Vec<GroupModel>, sqlx::Error> {
+        let query = format!("SELECT {} FROM \"groups\" {}", GROUP_COLUMNS, condition);
+        let mut statement = sqlx::query(&query);
+
+        for id in ids.iter() {
+            statement = statement.bind(id.to_hex());
+        }
+
+        let mut groups = statement.try_map(group_row).fetch_all(&self.pool).await?;
+
+        if groups.is_empty() {
+            return Ok(groups);
+        }
+
+        let query = format!(
+            "SELECT group_permissions.group_id, permissions.id, permissions.name, permissions.created_at
+            FROM group_permissions
+            JOIN permissions ON permissions.id = group_permissions.permission_id
+            WHERE group_permissions.group_id IN ({})
+            ORDER BY permissions.name",
+            placeholders(1, groups.len()),
+        );
+        let mut statement = sqlx::query(&query);
+
+        for group in groups.iter() {
+            statement = statement.bind(group._id.to_hex());
+        }
+
+        let permissions = statement
+            .try_map(|row: AnyRow| Ok((object_id(&row, "group_id")?, permission_row(row)?)))
+            .fetch_all(&self.pool)
+            .await?;
+
+        for (group, permission) in permissions.into_iter() {
+            if let Some(value) = groups.iter_mut().find(|value| value._id == group) {
+                value.permissions.push(permission);
+            }
+        }
+
+        Ok(groups)
+    }
+
+    async fn insert_group(&self, group: &GroupModel) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("INSERT INTO \"groups\" (id, name, can_read, can_write, can_delete, created_at) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(group._id.to_hex())
+            .bind(&group.name)
+            .bind(i16::from(group.actions.read))
+            .bind(i16::from(group.actions.write))
+            .bind(i16::from(group.actions.delete))
+            .bind(group.created_at.timestamp_millis())
+            .execute(&mut *tx)
+            .await?;
+
+        for permission in group.permissions.iter() {
+            sqlx::query("INSERT INTO group_permissions (group_id, permission_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                .bind(group._id.to_hex())
+                .bind(permission._id.to_hex())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Micro serviços que satisfazem `condition`, com seus prefixos.
+    /// Os IDs são ligados, em ordem, aos parâmetros da condição.
+    async fn select_micro_services(&self, condition: &str, ids: &[ObjectId]) -> Result<Vec<MicroServiceModel>, sqlx::Error> {
+        let query = format!("SELECT {} FROM micro_services {}", MICRO_SERVICE_COLUMNS, condition);
+        let mut statement = sqlx::query(&query);
+
+        for id in ids.iter() {
+            statement = statement.bind(id.to_hex());
+        }
+
+        let mut micro_services = statement.try_map(micro_service_row).fetch_all(&self.pool).await?;
+
+        if micro_services.is_empty() {
+            return Ok(micro_services);
+        }
+
+        let query = format!(
+            "SELECT micro_service_id, route FROM micro_service_routes WHERE micro_service_id IN ({}) ORDER BY position",
+            placeholders(1, micro_services.len()),
+        );
+        let mut statement = sqlx::query(&query);
+
+        for micro_service in micro_services.iter() {
+            statement = statement.bind(micro_service._id.to_hex());
+        }
+
+        let routes = statement
+            .try_map(|row: AnyRow| Ok((object_id(&row, "micro_service_id")?, row.try_get::<String, _>("route")?)))
+            .fetch_all(&self.pool)
+            .await?;
+
+        for (micro_service, route) in routes.into_iter() {
+            if let Some(value) = micro_services.iter_mut().find(|value| value._id == micro_service) {
+                value.routes.push(route);
+            }
+        }
+
+        Ok(micro_services)
+    }
+
+    async fn insert_micro_service(&self, micro_service: &MicroServiceModel) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("INSERT INTO micro_services (id, name, host, created_at) VALUES ($1, $2, $3, $4)")
+            .bind(micro_service._id.to_hex())
+            .bind(&micro_service.name)
+            .bind(&micro_service.host)
+            .bind(micro_service.created_at.timestamp_millis())
+            .execute(&mut *tx)
+            .await?;
+
+        insert_routes(&mut tx, &micro_service._id, &micro_service.routes).await?;
+
+        tx.commit().await
+    }
+
+    /// Retorna falso se o micro serviço não existir.
+    async fn update_micro_service(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("UPDATE micro_services SET name = $2, host = $3 WHERE id = $1")
+            .bind(id.to_hex())
+            .bind(name)
+            .bind(host)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM micro_service_routes WHERE micro_service_id = $1")
+            .bind(id.to_hex())
+            .execute(&mut *tx)
+            .await?;
+
+        insert_routes(&mut tx, id, routes).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Retorna falso se o micro serviço não existir.
+    async fn delete_micro_service(&self, id: &ObjectId) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM micro_service_routes WHERE micro_service_id = $1")
+            .bind(id.to_hex())
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM micro_services WHERE id = $1")
+            .bind(id.to_hex())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn upsert_failure(&self, key: &str, now: DateTime) -> Result<LoginAttemptModel, sqlx::Error> {
+        // Contador vencido equivale ao removido pelo índice TTL.
+        sqlx::query("DELETE FROM login_attempts WHERE key = $1 AND expires_at <= $2")
+            .bind(key)
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await?;
+
+        let query = format!(
+            "INSERT INTO login_attempts ({0}) VALUES ($1, $2, 1, $3, $3, $3)
+            ON CONFLICT (key) DO UPDATE SET failures = login_attempts.failures + 1, last_failure = $3
+            RETURNING {0}",
+            LOGIN_ATTEMPT_COLUMNS,
+        );
+
+        sqlx::query(&query)
+            .bind(ObjectId::new().to_hex())
+            .bind(key)
+            .bind(now.timestamp_millis())
+            .try_map(login_attempt_row)
+            .fetch_one(&self.pool)
+            .await
+    }
+}
+
+
+/// Valida se o erro foi causado por violação de índice único.
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e.is_unique_violation(),
+        _ => false,
+    }
+}
+
+
+/// Grava os prefixos do micro serviço na ordem informada.
+async fn insert_routes(connection: &mut AnyConnection, micro_service: &ObjectId, routes: &[String]) -> Result<(), sqlx::Error> {
+    for (position, route) in routes.iter().enumerate() {
+        sqlx::query("INSERT INTO micro_service_routes (micro_service_id, position, route) VALUES ($1, $2, $3)")
+            .bind(micro_service.to_hex())
+            .bind(position as i64)
+            .bind(route)
+            .execute(&mut *connection)
+            .await?;
+    }
+
+    Ok(())
+}
+
+
+/// Parâmetros `$n` numerados a partir de `start`, para os filtros `IN`.
+fn placeholders(start: usize, count: usize) -> String {
+    (start..start + count)
+        .map(|index| format!("${}", index))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+
+fn decode_error<E>(column: &str, error: E) -> sqlx::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: error.into(),
+    }
+}
+
+
+fn object_id(row: &AnyRow, column: &str) -> Result<ObjectId, sqlx::Error> {
+    let value: String = row.try_get(column)?;
+
+    ObjectId::parse_str(&value).map_err(|e| decode_error(column, e))
+}
+
+
+fn datetime(row: &AnyRow, column: &str) -> Result<DateTime, sqlx::Error> {
+    Ok(DateTime::from_millis(row.try_get(column)?))
+}
+
+
+fn optional_datetime(row: &AnyRow, column: &str) -> Result<Option<DateTime>, sqlx::Error> {
+    let value: Option<i64> = row.try_get(column)?;
+
+    Ok(value.map(DateTime::from_millis))
+}
+
+
+fn flag(row: &AnyRow, column: &str) -> Result<bool, sqlx::Error> {
+    Ok(row.try_get::<i64, _>(column)? != 0)
+}
+
+
+fn count(row: AnyRow) -> Result<i64, sqlx::Error> {
+    row.try_get(0)
+}
+
+
+fn user_row(row: AnyRow) -> Result<UserModel, sqlx::Error> {
+    Ok(UserModel {
+        _id: object_id(&row, "id")?,
+        username: row.try_get("username")?,
+        email: row.try_get("email")?,
+        password: row.try_get("password")?,
+        first_name: row.try_get("first_name")?,
+        last_name: row.try_get("last_name")?,
+        is_active: flag(&row, "is_active")?,
+        is_superuser: flag(&row, "is_superuser")?,
+        created_at: datetime(&row, "created_at")?,
+        last_login: optional_datetime(&row, "last_login")?,
+    })
+}
+
+
+/// As permissões são carregadas à parte.
+fn group_row(row: AnyRow) -> Result<GroupModel, sqlx::Error> {
+    Ok(GroupModel {
+        _id: object_id(&row, "id")?,
+        name: row.try_get("name")?,
+        permissions: Vec::new(),
+        actions: Actions {
+            read: flag(&row, "can_read")?,
+            write: flag(&row, "can_write")?,
+            delete: flag(&row, "can_delete")?,
+        },
+        created_at: datetime(&row, "created_at")?,
+    })
+}
+
+
+fn permission_row(row: AnyRow) -> Result<PermissionModel, sqlx::Error> {
+    Ok(PermissionModel {
+        _id: object_id(&row, "id")?,
+        name: row.try_get("name")?,
+        created_at: datetime(&row, "created_at")?,
+    })
+}
+
+
+/// Os prefixos são carregados à parte.
+fn micro_service_row(row: AnyRow) -> Result<MicroServiceModel, sqlx::Error> {
+    Ok(MicroServiceModel {
+        _id: object_id(&row, "id")?,
+        name: row.try_get("name")?,
+        host: row.try_get("host")?,
+        routes: Vec::new(),
+        created_at: datetime(&row, "created_at")?,
+    })
+}
+
+
+fn refresh_token_row(row: AnyRow) -> Result<RefreshTokenModel, sqlx::Error> {
+    Ok(RefreshTokenModel {
+        _id: object_id(&row, "id")?,
+        user: object_id(&row, "user_id")?,
+        family: object_id(&row, "family")?,
+        token_hash: row.try_get("token_hash")?,
+        created_at: datetime(&row, "created_at")?,
+        expires_at: datetime(&row, "expires_at")?,
+        rotated_at: optional_datetime(&row, "rotated_at")?,
+        revoked: flag(&row, "revoked")?,
+    })
+}
+
+
+fn session_row(row: AnyRow) -> Result<SessionModel, sqlx::Error> {
+    Ok(SessionModel {
+        _id: object_id(&row, "id")?,
+        user: object_id(&row, "user_id")?,
+        user_agent: row.try_get("user_agent")?,
+        ip: row.try_get("ip")?,
+        jti: row.try_get("jti")?,
+        token_expires_at: datetime(&row, "token_expires_at")?,
+        created_at: datetime(&row, "created_at")?,
+        last_seen: datetime(&row, "last_seen")?,
+        expires_at: datetime(&row, "expires_at")?,
+    })
+}
+
+
+fn login_attempt_row(row: AnyRow) -> Result<LoginAttemptModel, sqlx::Error> {
+    let failures: i64 = row.try_get("failures")?;
+
+    Ok(LoginAttemptModel {
+        _id: object_id(&row, "id")?,
+        key: row.try_get("key")?,
+        failures: u32::try_from(failures).map_err(|e| decode_error("failures", e))?,
+        last_failure: datetime(&row, "last_failure")?,
+        blocked_until: datetime(&row, "blocked_until")?,
+        expires_at: datetime(&row, "expires_at")?,
+    })
+}
+
+
+fn signing_key_row(row: AnyRow) -> Result<SigningKeyModel, sqlx::Error> {
+    let status: String = row.try_get("status")?;
+
+    Ok(SigningKeyModel {
+        _id: object_id(&row, "id")?,
+        kid: row.try_get("kid")?,
+        status: status.parse().map_err(|e: String| decode_error("status", e))?,
+        created_at: datetime(&row, "created_at")?,
+        retire_at: optional_datetime(&row, "retire_at")?,
+    })
+}
+
+
+#[async_trait]
+impl UserRepository for SqlStorage {
+    async fn find_by_username(&self, username: &str) -> Result<Option<UserModel>, AppError> {
+        let query = format!("SELECT {} FROM users WHERE username = $1", USER_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(username)
+            .try_map(user_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in users, cause {}", username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<UserModel>, AppError> {
+        let query = format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(id.to_hex())
+            .try_map(user_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in users, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self, is_active: Option<bool>, skip: u64, limit: i64) -> Result<Vec<UserModel>, AppError> {
+        let filter = match is_active {
+            Some(_) => "WHERE is_active = $3",
+            None => "",
+        };
+        let query = format!(
+            "SELECT {} FROM users {} ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+            USER_COLUMNS,
+            filter,
+        );
+        let mut statement = sqlx::query(&query)
+            .bind(limit.max(0))
+            .bind(skip as i64);
+
+        if let Some(value) = is_active {
+            statement = statement.bind(i16::from(value));
+        }
+
+        match statement
+            .try_map(user_row)
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter users, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn insert(&self, user: &UserModel) -> Result<(), AppError> {
+        let query = format!(
+            "INSERT INTO users ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            USER_COLUMNS,
+        );
+
+        match sqlx::query(&query)
+            .bind(user._id.to_hex())
+            .bind(&user.username)
+            .bind(&user.email)
+            .bind(&user.password)
+            .bind(&user.first_name)
+            .bind(&user.last_name)
+            .bind(i16::from(user.is_active))
+            .bind(i16::from(user.is_superuser))
+            .bind(user.created_at.timestamp_millis())
+            .bind(user.last_login.map(|value| value.timestamp_millis()))
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("username")),
+                Err(e) => {
+                    error!("Can not create user {}, cause {}", &user.username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn update(&self, id: &ObjectId, changes: &UserChanges) -> Result<Option<UserModel>, AppError> {
+        // Campos ausentes mantêm o valor atual.
+        let query = "UPDATE users SET
+            username = COALESCE($2, username),
+            email = COALESCE($3, email),
+            password = COALESCE($4, password),
+            first_name = COALESCE($5, first_name),
+            last_name = COALESCE($6, last_name),
+            is_active = COALESCE($7, is_active),
+            is_superuser = COALESCE($8, is_superuser)
+            WHERE id = $1";
+
+        match sqlx::query(query)
+            .bind(id.to_hex())
+            .bind(changes.username.clone())
+            .bind(changes.email.clone())
+            .bind(changes.password.clone())
+            .bind(changes.first_name.clone())
+            .bind(changes.last_name.clone())
+            .bind(changes.is_active.map(i16::from))
+            .bind(changes.is_superuser.map(i16::from))
+            .execute(&self.pool)
+            .await {
+                Ok(result) if result.rows_affected() == 0 => Ok(None),
+                Ok(_) => UserRepository::find_by_id(self, id).await,
+                Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("username")),
+                Err(e) => {
+                    error!("Can not update user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() == 1),
+                Err(e) => {
+                    error!("Can not delete user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn set_password(&self, username: &str, password: &str) -> Result<(), AppError> {
+        match sqlx::query("UPDATE users SET password = $2 WHERE username = $1")
+            .bind(username)
+            .bind(password)
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not update password of user {}, cause {}", username, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn record_login(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        match sqlx::query("UPDATE users SET last_login = $2 WHERE id = $1 AND is_active = 1")
+            .bind(id.to_hex())
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() == 1),
+                Err(e) => {
+                    error!("Can not record login of user {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl GroupRepository for SqlStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<GroupModel>, AppError> {
+        match self.select_groups("WHERE id = $1", &[*id]).await {
+            Ok(value) => Ok(value.into_iter().next()),
+            Err(e) => {
+                error!("Can not filter {} in groups, cause {}.", id, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<GroupModel>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let condition = format!("WHERE id IN ({})", placeholders(1, ids.len()));
+
+        match self.select_groups(&condition, ids).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                error!("Can not filter groups, cause {}", e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<GroupModel>, AppError> {
+        match self.select_groups("ORDER BY name", &[]).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                error!("Can not filter groups, cause {}", e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn insert(&self, group: &GroupModel) -> Result<(), AppError> {
+        match self.insert_group(group).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("group")),
+            Err(e) => {
+                error!("Can not create group {}, cause {}", &group.name, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match sqlx::query("SELECT COUNT(*) FROM \"groups\" WHERE id = $1")
+            .bind(id.to_hex())
+            .try_map(count)
+            .fetch_one(&self.pool)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not filter {} in groups, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn add_permission(&self, group: &ObjectId, permission: &PermissionModel) -> Result<(), AppError> {
+        // Apenas grupos existentes recebem a permissão.
+        let query = "INSERT INTO group_permissions (group_id, permission_id)
+            SELECT $1, $2 WHERE EXISTS (SELECT 1 FROM \"groups\" WHERE id = $1)
+            ON CONFLICT DO NOTHING";
+
+        match sqlx::query(query)
+            .bind(group.to_hex())
+            .bind(permission._id.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not add permission {} to group {}, cause {}", &permission.name, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_permission(&self, group: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("DELETE FROM group_permissions WHERE group_id = $1 AND permission_id = $2")
+            .bind(group.to_hex())
+            .bind(permission.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not remove permission {} from group {}, cause {}", permission, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl PermissionRepository for SqlStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<PermissionModel>, AppError> {
+        let query = format!("SELECT {} FROM permissions WHERE id = $1", PERMISSION_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(id.to_hex())
+            .try_map(permission_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter {} in permissions, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_many(&self, ids: &[ObjectId]) -> Result<Vec<PermissionModel>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT {} FROM permissions WHERE id IN ({})",
+            PERMISSION_COLUMNS,
+            placeholders(1, ids.len()),
+        );
+        let mut statement = sqlx::query(&query);
+
+        for id in ids.iter() {
+            statement = statement.bind(id.to_hex());
+        }
+
+        match statement
+            .try_map(permission_row)
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter permissions, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self) -> Result<Vec<PermissionModel>, AppError> {
+        let query = format!("SELECT {} FROM permissions ORDER BY name", PERMISSION_COLUMNS);
+
+        match sqlx::query(&query)
+            .try_map(permission_row)
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter permissions, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn insert(&self, permission: &PermissionModel) -> Result<(), AppError> {
+        let query = format!("INSERT INTO permissions ({}) VALUES ($1, $2, $3)", PERMISSION_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(permission._id.to_hex())
+            .bind(&permission.name)
+            .bind(permission.created_at.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("permission")),
+                Err(e) => {
+                    error!("Can not create permission {}, cause {}", &permission.name, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl MicroServiceRepository for SqlStorage {
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<MicroServiceModel>, AppError> {
+        match self.select_micro_services("WHERE id = $1", &[*id]).await {
+            Ok(value) => Ok(value.into_iter().next()),
+            Err(e) => {
+                error!("Can not filter {} in micro services, cause {}.", id, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<MicroServiceModel>, AppError> {
+        match self.select_micro_services("ORDER BY name", &[]).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                error!("Can not filter micro services, cause {}", e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn insert(&self, micro_service: &MicroServiceModel) -> Result<(), AppError> {
+        match self.insert_micro_service(micro_service).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("micro service")),
+            Err(e) => {
+                error!("Can not register micro service {}, cause {}", &micro_service.name, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn update(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<Option<MicroServiceModel>, AppError> {
+        match self.update_micro_service(id, name, host, routes).await {
+            Ok(true) => MicroServiceRepository::find_by_id(self, id).await,
+            Ok(false) => Ok(None),
+            Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("micro service")),
+            Err(e) => {
+                error!("Can not update micro service {}, cause {}", id, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match self.delete_micro_service(id).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                error!("Can not deregister micro service {}, cause {}", id, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn exists(&self, id: &ObjectId) -> Result<bool, AppError> {
+        match sqlx::query("SELECT COUNT(*) FROM micro_services WHERE id = $1")
+            .bind(id.to_hex())
+            .try_map(count)
+            .fetch_one(&self.pool)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not filter {} in micro services, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn routes_taken(&self, except: Option<&ObjectId>, routes: &[String]) -> Result<bool, AppError> {
+        if routes.is_empty() {
+            return Ok(false);
+        }
+
+        let mut query = format!(
+            "SELECT COUNT(*) FROM micro_service_routes WHERE route IN ({})",
+            placeholders(1, routes.len()),
+        );
+
+        if except.is_some() {
+            query.push_str(&format!(" AND micro_service_id <> ${}", routes.len() + 1));
+        }
+
+        let mut statement = sqlx::query(&query);
+
+        for route in routes.iter() {
+            statement = statement.bind(route);
+        }
+        if let Some(value) = except {
+            statement = statement.bind(value.to_hex());
+        }
+
+        match statement
+            .try_map(count)
+            .fetch_one(&self.pool)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not verify routes of micro services, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl RelationshipRepository for SqlStorage {
+    async fn groups_of(&self, user: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        match sqlx::query("SELECT group_id FROM users_groups WHERE user_id = $1")
+            .bind(user.to_hex())
+            .try_map(|row: AnyRow| object_id(&row, "group_id"))
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter groups of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn add_user(&self, group: &ObjectId, user: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("INSERT INTO users_groups (user_id, group_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(user.to_hex())
+            .bind(group.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not add user {} to group {}, cause {}", user, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_user(&self, group: &ObjectId, user: &ObjectId) -> Result<bool, AppError> {
+        match sqlx::query("DELETE FROM users_groups WHERE user_id = $1 AND group_id = $2")
+            .bind(user.to_hex())
+            .bind(group.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() > 0),
+                Err(e) => {
+                    error!("Can not remove user {} from group {}, cause {}", user, group, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn remove_user_everywhere(&self, user: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("DELETE FROM users_groups WHERE user_id = $1")
+            .bind(user.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete groups relationship of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn permissions_of(&self, micro_service: &ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        match sqlx::query("SELECT permission_id FROM micro_service_permission WHERE micro_service_id = $1")
+            .bind(micro_service.to_hex())
+            .try_map(|row: AnyRow| object_id(&row, "permission_id"))
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter permissions of micro service {}, cause {}", micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn link(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("INSERT INTO micro_service_permission (micro_service_id, permission_id) VALUES ($1, $2)")
+            .bind(micro_service.to_hex())
+            .bind(permission.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) if is_unique_violation(&e) => Err(AppError::Conflict("link")),
+                Err(e) => {
+                    error!("Can not link permission {} to micro service {}, cause {}", permission, micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn unlink(&self, micro_service: &ObjectId, permission: &ObjectId) -> Result<bool, AppError> {
+        match sqlx::query("DELETE FROM micro_service_permission WHERE micro_service_id = $1 AND permission_id = $2")
+            .bind(micro_service.to_hex())
+            .bind(permission.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() == 1),
+                Err(e) => {
+                    error!("Can not unlink permission {} from micro service {}, cause {}", permission, micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn unlink_everywhere(&self, micro_service: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("DELETE FROM micro_service_permission WHERE micro_service_id = $1")
+            .bind(micro_service.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete permissions relationship of micro service {}, cause {}", micro_service, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl TokenRepository for SqlStorage {
+    async fn insert_refresh(&self, token: &RefreshTokenModel) -> Result<(), AppError> {
+        self.purge("refresh_tokens", token.created_at).await;
+
+        let query = format!(
+            "INSERT INTO refresh_tokens ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            REFRESH_TOKEN_COLUMNS,
+        );
+
+        match sqlx::query(&query)
+            .bind(token._id.to_hex())
+            .bind(token.user.to_hex())
+            .bind(token.family.to_hex())
+            .bind(&token.token_hash)
+            .bind(token.created_at.timestamp_millis())
+            .bind(token.expires_at.timestamp_millis())
+            .bind(token.rotated_at.map(|value| value.timestamp_millis()))
+            .bind(i16::from(token.revoked))
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not store refresh token for user {}, cause {}", token.user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_refresh(&self, token_hash: &str) -> Result<Option<RefreshTokenModel>, AppError> {
+        let query = format!("SELECT {} FROM refresh_tokens WHERE token_hash = $1", REFRESH_TOKEN_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(token_hash)
+            .try_map(refresh_token_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter refresh token, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn mark_rotated(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        let query = "UPDATE refresh_tokens SET rotated_at = $2
+            WHERE id = $1 AND rotated_at IS NULL AND revoked = 0";
+
+        match sqlx::query(query)
+            .bind(id.to_hex())
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() == 1),
+                Err(e) => {
+                    error!("Can not rotate refresh token, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn revoke_family(&self, family: &ObjectId) -> Result<u64, AppError> {
+        match sqlx::query("UPDATE refresh_tokens SET revoked = 1 WHERE family = $1 AND revoked = 0")
+            .bind(family.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected()),
+                Err(e) => {
+                    error!("Can not revoke refresh token family {}, cause {}", family, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn revoke_user(&self, user: &ObjectId) -> Result<u64, AppError> {
+        match sqlx::query("UPDATE refresh_tokens SET revoked = 1 WHERE user_id = $1 AND revoked = 0")
+            .bind(user.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected()),
+                Err(e) => {
+                    error!("Can not revoke refresh tokens of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn insert_revoked(&self, token: &RevokedTokenModel) -> Result<(), AppError> {
+        self.purge("revoked_tokens", token.revoked_at).await;
+
+        // Já revogado anteriormente.
+        let query = "INSERT INTO revoked_tokens (id, jti, user_id, revoked_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (jti) DO NOTHING";
+
+        match sqlx::query(query)
+            .bind(token._id.to_hex())
+            .bind(&token.jti)
+            .bind(token.user.to_hex())
+            .bind(token.revoked_at.timestamp_millis())
+            .bind(token.expires_at.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not revoke access token {}, cause {}", &token.jti, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        match sqlx::query("SELECT COUNT(*) FROM revoked_tokens WHERE jti = $1")
+            .bind(jti)
+            .try_map(count)
+            .fetch_one(&self.pool)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not verify revoked token {}, cause {}", jti, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl SessionRepository for SqlStorage {
+    async fn insert(&self, session: &SessionModel) -> Result<(), AppError> {
+        self.purge("sessions", session.created_at).await;
+
+        let query = format!(
+            "INSERT INTO sessions ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            SESSION_COLUMNS,
+        );
+
+        match sqlx::query(&query)
+            .bind(session._id.to_hex())
+            .bind(session.user.to_hex())
+            .bind(session.user_agent.clone())
+            .bind(session.ip.clone())
+            .bind(&session.jti)
+            .bind(session.token_expires_at.timestamp_millis())
+            .bind(session.created_at.timestamp_millis())
+            .bind(session.last_seen.timestamp_millis())
+            .bind(session.expires_at.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not store session for user {}, cause {}", session.user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_by_id(&self, id: &ObjectId) -> Result<Option<SessionModel>, AppError> {
+        let query = format!("SELECT {} FROM sessions WHERE id = $1 AND expires_at > $2", SESSION_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(id.to_hex())
+            .bind(DateTime::now().timestamp_millis())
+            .try_map(session_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter session {}, cause {}.", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn list(&self, user: &ObjectId) -> Result<Vec<SessionModel>, AppError> {
+        let query = format!(
+            "SELECT {} FROM sessions WHERE user_id = $1 AND expires_at > $2 ORDER BY last_seen DESC",
+            SESSION_COLUMNS,
+        );
+
+        match sqlx::query(&query)
+            .bind(user.to_hex())
+            .bind(DateTime::now().timestamp_millis())
+            .try_map(session_row)
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter sessions of user {}, cause {}", user, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn renew(&self, id: &ObjectId, jti: &str, token_expires_at: DateTime, now: DateTime, expires_at: DateTime) -> Result<bool, AppError> {
+        let query = "UPDATE sessions SET jti = $2, token_expires_at = $3, last_seen = $4, expires_at = $5
+            WHERE id = $1 AND expires_at > $4";
+
+        match sqlx::query(query)
+            .bind(id.to_hex())
+            .bind(jti)
+            .bind(token_expires_at.timestamp_millis())
+            .bind(now.timestamp_millis())
+            .bind(expires_at.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() == 1),
+                Err(e) => {
+                    error!("Can not renew session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn touch(&self, id: &ObjectId, now: DateTime) -> Result<(), AppError> {
+        match sqlx::query("UPDATE sessions SET last_seen = $2 WHERE id = $1")
+            .bind(id.to_hex())
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not touch session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, id: &ObjectId) -> Result<(), AppError> {
+        match sqlx::query("DELETE FROM sessions WHERE id = $1")
+            .bind(id.to_hex())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not delete session {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl LoginAttemptRepository for SqlStorage {
+    async fn find_blocked(&self, keys: &[String], now: DateTime) -> Result<Option<LoginAttemptModel>, AppError> {
+        if keys.is_empty() {
+            return Ok(None);
+        }
+
+        let query = format!(
+            "SELECT {} FROM login_attempts WHERE blocked_until > $1 AND key IN ({}) ORDER BY blocked_until DESC LIMIT 1",
+            LOGIN_ATTEMPT_COLUMNS,
+            placeholders(2, keys.len()),
+        );
+        let mut statement = sqlx::query(&query).bind(now.timestamp_millis());
+
+        for key in keys.iter() {
+            statement = statement.bind(key);
+        }
+
+        match statement
+            .try_map(login_attempt_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not verify login attempts, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn record_failure(&self, key: &str, now: DateTime) -> Result<LoginAttemptModel, AppError> {
+        match self.upsert_failure(key, now).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                error!("Can not record login failure of {}, cause {}", key, e);
+                Err(AppError::Database)
+            }
+        }
+    }
+
+    async fn block(&self, id: &ObjectId, blocked_until: DateTime, expires_at: DateTime) -> Result<(), AppError> {
+        match sqlx::query("UPDATE login_attempts SET blocked_until = $2, expires_at = $3 WHERE id = $1")
+            .bind(id.to_hex())
+            .bind(blocked_until.timestamp_millis())
+            .bind(expires_at.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not block login attempts {}, cause {}", id, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool, AppError> {
+        match sqlx::query("DELETE FROM login_attempts WHERE key = $1")
+            .bind(key)
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected() > 0),
+                Err(e) => {
+                    error!("Can not reset login failures of {}, cause {}", key, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
+
+
+#[async_trait]
+impl SigningKeyRepository for SqlStorage {
+    async fn list(&self) -> Result<Vec<SigningKeyModel>, AppError> {
+        let query = format!("SELECT {} FROM signing_keys ORDER BY created_at", SIGNING_KEY_COLUMNS);
+
+        match sqlx::query(&query)
+            .try_map(signing_key_row)
+            .fetch_all(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn find_by_kid(&self, kid: &str) -> Result<Option<SigningKeyModel>, AppError> {
+        let query = format!("SELECT {} FROM signing_keys WHERE kid = $1", SIGNING_KEY_COLUMNS);
+
+        match sqlx::query(&query)
+            .bind(kid)
+            .try_map(signing_key_row)
+            .fetch_optional(&self.pool)
+            .await {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    error!("Can not filter signing key {}, cause {}", kid, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn has_active(&self) -> Result<bool, AppError> {
+        match sqlx::query("SELECT COUNT(*) FROM signing_keys WHERE status = $1")
+            .bind(KeyStatus::Active.to_string())
+            .try_map(count)
+            .fetch_one(&self.pool)
+            .await {
+                Ok(count) => Ok(count > 0),
+                Err(e) => {
+                    error!("Can not count active signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn demote_active(&self, kid: &str, retire_at: DateTime) -> Result<u64, AppError> {
+        match sqlx::query("UPDATE signing_keys SET status = $1, retire_at = $2 WHERE status = $3 AND kid <> $4")
+            .bind(KeyStatus::Verify.to_string())
+            .bind(retire_at.timestamp_millis())
+            .bind(KeyStatus::Active.to_string())
+            .bind(kid)
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected()),
+                Err(e) => {
+                    error!("Can not demote signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn retire_due(&self, now: DateTime) -> Result<u64, AppError> {
+        match sqlx::query("UPDATE signing_keys SET status = $1 WHERE status = $2 AND retire_at <= $3")
+            .bind(KeyStatus::Retired.to_string())
+            .bind(KeyStatus::Verify.to_string())
+            .bind(now.timestamp_millis())
+            .execute(&self.pool)
+            .await {
+                Ok(result) => Ok(result.rows_affected()),
+                Err(e) => {
+                    error!("Can not retire signing keys, cause {}", e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+
+    async fn upsert(&self, kid: &str, status: KeyStatus, retire_at: Option<DateTime>, now: DateTime) -> Result<(), AppError> {
+        let query = format!(
+            "INSERT INTO signing_keys ({}) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (kid) DO UPDATE SET status = excluded.status, retire_at = excluded.retire_at",
+            SIGNING_KEY_COLUMNS,
+        );
+
+        match sqlx::query(&query)
+            .bind(ObjectId::new().to_hex())
+            .bind(kid)
+            .bind(status.to_string())
+            .bind(now.timestamp_millis())
+            .bind(retire_at.map(|value| value.timestamp_millis()))
+            .execute(&self.pool)
+            .await {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    error!("Can not write signing key {}, cause {}", kid, e);
+                    Err(AppError::Database)
+                }
+            }
+    }
+}
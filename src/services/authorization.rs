@@ -0,0 +1,157 @@
+use std::fmt;
+
+use actix_web::http::Method;
+use bson::oid::ObjectId;
+use log::debug;
+use serde::Serialize;
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::groups::{Actions, GroupModel};
+use crate::models::users::UserModel;
+
+
+/// Ação pretendida sobre um recurso protegido.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Read,
+    Write,
+    Delete,
+} impl Action {
+    /// Converte o método HTTP na ação correspondente.
+    pub fn from_method(method: &Method) -> Self {
+        match *method {
+            Method::DELETE => Action::Delete,
+            Method::POST | Method::PUT | Method::PATCH => Action::Write,
+            _ => Action::Read,
+        }
+    }
+
+    /// Valida se as ações do grupo permitem esta ação.
+    pub fn allowed_by(&self, actions: &Actions) -> bool {
+        match self {
+            Action::Read => actions.read,
+            Action::Write => actions.write,
+            Action::Delete => actions.delete,
+        }
+    }
+} impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Read => "read",
+            Action::Write => "write",
+            Action::Delete => "delete",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+
+/// Motivo estruturado da recusa de acesso.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccessDenied {
+    pub reason: String,
+    pub permission: String,
+    pub action: Action,
+} impl From<AccessDenied> for AppError {
+    fn from(denied: AccessDenied) -> Self {
+        AppError::PermissionDenied(denied)
+    }
+}
+
+
+#[derive(Clone)]
+pub struct AuthorizationService{
+    storage: Storage,
+} impl AuthorizationService {
+    pub fn new(storage: Storage) -> Self {
+        AuthorizationService {
+            storage,
+        }
+    }
+
+    /// Captura os grupos aos quais o usuário pertence.
+    pub async fn groups_of(&self, user: &ObjectId) -> Result<Vec<GroupModel>, AppError> {
+        let ids = self.storage.relationships.groups_of(user).await?;
+
+        self.storage.groups.find_many(&ids).await
+    }
+
+    /// Valida se o usuário pode executar a ação sob a permissão.
+    /// Superusuários têm acesso irrestrito.
+    pub async fn can(&self, user: &UserModel, permission: &str, action: Action) -> Result<(), AppError> {
+        self.can_all(user, &[permission.to_string()], action).await
+    }
+
+    /// Valida se o usuário pode executar a ação sob todas as permissões.
+    /// Os grupos do usuário são consultados uma única vez.
+    pub async fn can_all(&self, user: &UserModel, permissions: &[String], action: Action) -> Result<(), AppError> {
+        // Evita consultar os grupos quando não forem necessários.
+        if !user.is_active || user.is_superuser || permissions.is_empty() {
+            return evaluate(user, &[], permissions, action).map_err(AppError::from);
+        }
+
+        let groups = self.groups_of(&user._id).await?;
+
+        evaluate(user, &groups, permissions, action).map_err(AppError::from)
+    }
+}
+
+
+/// Valida as permissões do usuário contra grupos já carregados.
+pub fn evaluate(user: &UserModel, groups: &[GroupModel], permissions: &[String], action: Action) -> Result<(), AccessDenied> {
+    let denied = |reason: &str, permission: &str| AccessDenied {
+        reason: reason.to_string(),
+        permission: permission.to_string(),
+        action,
+    };
+
+    if !user.is_active {
+        return Err(denied("User is not active.", &permissions.join(",")));
+    }
+    if user.is_superuser {
+        debug!("Superuser {} bypassed permissions {}.", &user.username, permissions.join(","));
+        return Ok(());
+    }
+
+    for permission in permissions.iter() {
+        let granted = groups.iter().any(|group| {
+            action.allowed_by(&group.actions)
+                && group.permissions.iter().any(|perm| &perm.name == permission)
+        });
+
+        if !granted {
+            debug!("User {} denied {} on {}.", &user.username, action, permission);
+            return Err(denied("Missing permission for this action.", permission));
+        }
+    }
+
+    Ok(())
+}
+
+
+/// Lista as permissões efetivas no formato `permissão:ação`.
+pub fn effective_permissions(groups: &[GroupModel]) -> Vec<String> {
+    let mut effective: Vec<String> = Vec::new();
+
+    for group in groups.iter() {
+        for action in [Action::Read, Action::Write, Action::Delete] {
+            if !action.allowed_by(&group.actions) {
+                continue;
+            }
+
+            for permission in group.permissions.iter() {
+                let item = format!("{}:{}", &permission.name, action);
+
+                if !effective.contains(&item) {
+                    effective.push(item);
+                }
+            }
+        }
+    }
+
+    effective.sort();
+    effective
+}
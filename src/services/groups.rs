@@ -0,0 +1,88 @@
+use bson::oid::ObjectId;
+use log::{debug, info};
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::groups::{GroupModel, GroupSerialize};
+use crate::models::permissions::PermissionModel;
+
+
+#[derive(Clone)]
+pub struct GroupService{
+    storage: Storage,
+} impl GroupService {
+    pub fn new(storage: Storage) -> Self {
+        GroupService {
+            storage,
+        }
+    }
+
+    /// Captura o grupo pelo ID.
+    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Option<GroupSerialize>, AppError> {
+        let group = self.storage.groups.find_by_id(id).await?;
+
+        debug!("Try to get group {} in database.", id);
+        Ok(group.as_ref().map(GroupSerialize::from))
+    }
+
+    /// Lista os grupos em ordem alfabética.
+    pub async fn list(&self) -> Result<Vec<GroupSerialize>, AppError> {
+        let groups = self.storage.groups.list().await?;
+
+        Ok(groups.iter().map(GroupSerialize::from).collect())
+    }
+
+    /// Cadastra um novo grupo.
+    pub async fn create(&self, group: &GroupModel) -> Result<(), AppError> {
+        self.storage.groups.insert(group).await?;
+
+        info!("Created group {}.", &group.name);
+        Ok(())
+    }
+
+    /// Adiciona a permissão ao grupo, ignorando se já estiver presente.
+    pub async fn add_permission(&self, group: &ObjectId, permission: &PermissionModel) -> Result<(), AppError> {
+        self.exists(group).await?;
+        self.storage.groups.add_permission(group, permission).await?;
+
+        info!("Added permission {} to group {}.", &permission.name, group);
+        Ok(())
+    }
+
+    /// Remove a permissão do grupo.
+    pub async fn remove_permission(&self, group: &ObjectId, permission: &ObjectId) -> Result<(), AppError> {
+        self.exists(group).await?;
+        self.storage.groups.remove_permission(group, permission).await?;
+
+        info!("Removed permission {} from group {}.", permission, group);
+        Ok(())
+    }
+
+    /// Relaciona o usuário ao grupo, ignorando se já estiver relacionado.
+    pub async fn add_user(&self, group: &ObjectId, user: &ObjectId) -> Result<(), AppError> {
+        self.exists(group).await?;
+
+        if self.storage.users.find_by_id(user).await?.is_none() {
+            return Err(AppError::NotFound("user"));
+        }
+
+        self.storage.relationships.add_user(group, user).await?;
+
+        info!("Added user {} to group {}.", user, group);
+        Ok(())
+    }
+
+    /// Remove o relacionamento entre o usuário e o grupo.
+    /// Retorna falso se o relacionamento não existir.
+    pub async fn remove_user(&self, group: &ObjectId, user: &ObjectId) -> Result<bool, AppError> {
+        self.storage.relationships.remove_user(group, user).await
+    }
+
+    /// Valida se o grupo existe.
+    async fn exists(&self, group: &ObjectId) -> Result<(), AppError> {
+        match self.storage.groups.exists(group).await? {
+            true => Ok(()),
+            false => Err(AppError::NotFound("group")),
+        }
+    }
+}
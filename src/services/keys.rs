@@ -0,0 +1,124 @@
+use std::fmt;
+
+use log::{debug, info, error};
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::keys::{KeyStatus, SigningKeyModel};
+use crate::tools::keys;
+
+
+/// Motivos pelos quais a troca de chaves pode falhar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// O material da chave não existe neste processo.
+    Unavailable,
+    /// A chave não está registrada.
+    NotFound,
+    /// A chave ativa não pode ser aposentada.
+    Active,
+    /// Falha ao consultar ou gravar no banco de dados.
+    Storage,
+} impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            KeyError::Unavailable => "key is not available in `jwt_keys_dir`",
+            KeyError::NotFound => "key not found",
+            KeyError::Active => "active key can not be retired, promote another key first",
+            KeyError::Storage => "can not access signing keys",
+        };
+
+        write!(f, "{}", reason)
+    }
+} impl From<AppError> for KeyError {
+    fn from(_: AppError) -> Self {
+        KeyError::Storage
+    }
+}
+
+
+#[derive(Clone)]
+pub struct KeyService{
+    storage: Storage,
+} impl KeyService {
+    pub fn new(storage: Storage) -> Self {
+        KeyService {
+            storage,
+        }
+    }
+
+    /// Lista o estado de todas as chaves registradas.
+    pub async fn list(&self) -> Option<Vec<SigningKeyModel>> {
+        self.storage.signing_keys.list().await.ok()
+    }
+
+    /// Promove a chave para ativa.
+    /// A chave ativa anterior passa a apenas validar até `retire_after` segundos.
+    pub async fn promote(&self, kid: &str, retire_after: u64) -> Result<(), KeyError> {
+        let available = keys::available();
+
+        if !available.iter().any(|value| value == kid) {
+            return Err(KeyError::Unavailable);
+        }
+
+        let now = DateTime::now();
+        let retire_at = DateTime::from_millis(now.timestamp_millis() + (retire_after as i64) * 1000);
+
+        // Sem chave ativa registrada, quem assina hoje é a chave configurada.
+        let registered = self.storage.signing_keys.has_active().await?;
+
+        if !registered && available[0] != kid {
+            self.storage.signing_keys.upsert(&available[0], KeyStatus::Verify, Some(retire_at), now).await?;
+        }
+
+        let demoted = self.storage.signing_keys.demote_active(kid, retire_at).await?;
+
+        debug!("Demoted {} signing keys.", demoted);
+        self.storage.signing_keys.upsert(kid, KeyStatus::Active, None, now).await?;
+        info!("Signing key {} promoted, previous keys retire at {}.", kid, retire_at);
+
+        Ok(())
+    }
+
+    /// Aposenta a chave imediatamente, tokens assinados com ela deixam de valer.
+    pub async fn retire(&self, kid: &str) -> Result<(), KeyError> {
+        if keys::active().is_some_and(|key| key.kid == kid) {
+            return Err(KeyError::Active);
+        }
+
+        let known = self.storage.signing_keys.find_by_kid(kid).await?.is_some();
+
+        if !known && !keys::available().iter().any(|value| value == kid) {
+            return Err(KeyError::NotFound);
+        }
+
+        let now = DateTime::now();
+
+        self.storage.signing_keys.upsert(kid, KeyStatus::Retired, Some(now), now).await?;
+        info!("Signing key {} retired.", kid);
+
+        Ok(())
+    }
+
+    /// Aposenta as chaves vencidas e atualiza o chaveiro do processo.
+    pub async fn sync(&self) -> bool {
+        match self.storage.signing_keys.retire_due(DateTime::now()).await {
+            Ok(count) if count > 0 => info!("Retired {} signing keys on schedule.", count),
+            _ => (),
+        };
+
+        let states = match self.list().await {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match keys::apply(&states) {
+            Ok(_) => true,
+            Err(e) => {
+                error!("Can not apply signing keys, keeping current keyring. Cause: {}", e);
+                false
+            }
+        }
+    }
+}
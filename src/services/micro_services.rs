@@ -0,0 +1,79 @@
+use bson::oid::ObjectId;
+use log::{debug, info};
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::micro_services::{MicroServiceModel, MicroServiceSerialize};
+
+
+#[derive(Clone)]
+pub struct RegistryService{
+    storage: Storage,
+} impl RegistryService {
+    pub fn new(storage: Storage) -> Self {
+        RegistryService {
+            storage,
+        }
+    }
+
+    /// Captura o micro serviço pelo ID.
+    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Option<MicroServiceSerialize>, AppError> {
+        let micro_service = self.storage.micro_services.find_by_id(id).await?;
+
+        debug!("Try to get micro service {} in database.", id);
+        Ok(micro_service.as_ref().map(MicroServiceSerialize::from))
+    }
+
+    /// Lista todos os micro serviços cadastrados.
+    pub async fn list(&self) -> Result<Vec<MicroServiceModel>, AppError> {
+        self.storage.micro_services.list().await
+    }
+
+    /// Cadastra um novo micro serviço.
+    pub async fn create(&self, micro_service: &MicroServiceModel) -> Result<(), AppError> {
+        self.check_routes(None, &micro_service.routes).await?;
+        self.storage.micro_services.insert(micro_service).await?;
+
+        info!("Registered micro service {}.", &micro_service.name);
+        Ok(())
+    }
+
+    /// Altera o cadastro do micro serviço.
+    /// Retorna o cadastro já atualizado, se existir.
+    pub async fn update(&self, id: &ObjectId, name: &str, host: &str, routes: &[String]) -> Result<Option<MicroServiceSerialize>, AppError> {
+        self.check_routes(Some(id), routes).await?;
+
+        let micro_service = self.storage.micro_services.update(id, name, host, routes).await?;
+
+        debug!("Updated micro service {}.", id);
+        Ok(micro_service.as_ref().map(MicroServiceSerialize::from))
+    }
+
+    /// Remove o micro serviço e o relacionamento com suas permissões.
+    /// Retorna falso se o micro serviço não existir.
+    pub async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        let deleted = self.storage.micro_services.delete(id).await?;
+
+        if deleted {
+            info!("Deregistered micro service {}.", id);
+
+            if self.storage.relationships.unlink_everywhere(id).await.is_ok() {
+                debug!("Deleted permissions relationship of micro service {}.", id);
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Valida se os prefixos já pertencem a outro micro serviço.
+    async fn check_routes(&self, id: Option<&ObjectId>, routes: &[String]) -> Result<(), AppError> {
+        if routes.is_empty() {
+            return Ok(());
+        }
+
+        match self.storage.micro_services.routes_taken(id, routes).await? {
+            true => Err(AppError::Conflict("route")),
+            false => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bson::oid::ObjectId;
+use log::{debug, info};
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::permissions::{PermissionModel, PermissionSerialize};
+
+
+#[derive(Clone)]
+pub struct PermissionService{
+    storage: Storage,
+} impl PermissionService {
+    pub fn new(storage: Storage) -> Self {
+        PermissionService {
+            storage,
+        }
+    }
+
+    /// Captura a permissão pelo ID.
+    pub async fn get_model_by_id(&self, id: &ObjectId) -> Result<Option<PermissionModel>, AppError> {
+        let permission = self.storage.permissions.find_by_id(id).await?;
+
+        debug!("Try to get permission {} in database.", id);
+        Ok(permission)
+    }
+
+    /// Lista as permissões em ordem alfabética.
+    pub async fn list(&self) -> Result<Vec<PermissionSerialize>, AppError> {
+        let permissions = self.storage.permissions.list().await?;
+
+        Ok(permissions.iter().map(PermissionSerialize::from).collect())
+    }
+
+    /// Cadastra uma nova permissão.
+    pub async fn create(&self, permission: &PermissionModel) -> Result<(), AppError> {
+        self.storage.permissions.insert(permission).await?;
+
+        info!("Created permission {}.", &permission.name);
+        Ok(())
+    }
+
+    /// Relaciona a permissão a um micro serviço.
+    /// Ambos precisam existir para o relacionamento ser criado.
+    pub async fn link_micro_service(&self, permission: &ObjectId, micro_service: &ObjectId) -> Result<(), AppError> {
+        if self.get_model_by_id(permission).await?.is_none() {
+            return Err(AppError::NotFound("permission"));
+        }
+        if !self.storage.micro_services.exists(micro_service).await? {
+            return Err(AppError::NotFound("micro service"));
+        }
+
+        self.storage.relationships.link(micro_service, permission).await?;
+
+        info!("Linked permission {} to micro service {}.", permission, micro_service);
+        Ok(())
+    }
+
+    /// Remove o relacionamento entre a permissão e o micro serviço.
+    /// Retorna falso se o relacionamento não existir.
+    pub async fn unlink_micro_service(&self, permission: &ObjectId, micro_service: &ObjectId) -> Result<bool, AppError> {
+        self.storage.relationships.unlink(micro_service, permission).await
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::time::Duration;
+
+use bson::oid::ObjectId;
+use log::{debug, error};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{Body, Client, Method, Response};
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::micro_services::MicroServiceModel;
+use crate::settings::Settings;
+
+
+/// Cabeçalhos que valem apenas para uma conexão e não devem ser repassados.
+pub const HOP_BY_HOP: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+];
+
+
+/// Falhas ao encaminhar a requisição ao micro serviço.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyError {
+    /// O micro serviço não respondeu a tempo.
+    Timeout,
+    /// O micro serviço está inacessível ou respondeu de forma inválida.
+    Unavailable,
+} impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ProxyError::Timeout => "upstream service timed out",
+            ProxyError::Unavailable => "upstream service unavailable",
+        };
+
+        write!(f, "{}", reason)
+    }
+} impl From<ProxyError> for AppError {
+    fn from(error: ProxyError) -> Self {
+        AppError::Upstream {
+            timeout: error == ProxyError::Timeout,
+        }
+    }
+}
+
+
+/// Requisição já traduzida para ser enviada ao micro serviço.
+pub struct Upstream {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<Body>,
+}
+
+
+#[derive(Clone)]
+pub struct ProxyService{
+    storage: Storage,
+    client: Client,
+} impl ProxyService {
+    pub fn new(storage: Storage, settings: &Settings) -> Self {
+        let timeout = Duration::from_secs(settings.proxy_timeout);
+        // Redirecionamentos são devolvidos ao cliente, não seguidos.
+        let client = Client::builder()
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .redirect(Policy::none())
+            .build()
+            .expect("Can not build proxy HTTP client.");
+
+        ProxyService {
+            storage,
+            client,
+        }
+    }
+
+    /// Encontra o micro serviço dono do caminho.
+    /// Vence o prefixo mais longo entre os cadastrados.
+    pub async fn resolve(&self, path: &str) -> Result<Option<MicroServiceModel>, AppError> {
+        let micro_services = self.storage.micro_services.list().await?;
+
+        let mut found: Option<(usize, MicroServiceModel)> = None;
+
+        for micro_service in micro_services.into_iter() {
+            let length = micro_service.routes
+                .iter()
+                .filter(|route| owns_path(route, path))
+                .map(|route| route.len())
+                .max();
+
+            if let Some(length) = length {
+                if found.as_ref().is_none_or(|(best, _)| length > *best) {
+                    found = Some((length, micro_service));
+                }
+            }
+        }
+
+        Ok(found.map(|(_, micro_service)| micro_service))
+    }
+
+    /// Captura os nomes das permissões relacionadas ao micro serviço.
+    pub async fn permissions_of(&self, micro_service: &ObjectId) -> Result<Vec<String>, AppError> {
+        let ids = self.storage.relationships.permissions_of(micro_service).await?;
+        let permissions = self.storage.permissions.find_many(&ids).await?;
+
+        Ok(permissions.into_iter().map(|permission| permission.name).collect())
+    }
+
+    /// Envia a requisição ao micro serviço.
+    pub async fn forward(&self, upstream: Upstream) -> Result<Response, ProxyError> {
+        debug!("Forwarding {} {}.", &upstream.method, &upstream.url);
+
+        let mut request = self.client
+            .request(upstream.method, &upstream.url)
+            .headers(upstream.headers);
+
+        if let Some(body) = upstream.body {
+            request = request.body(body);
+        }
+
+        match request.send().await {
+            Ok(response) => Ok(response),
+            Err(e) if e.is_timeout() => {
+                error!("Upstream {} timed out, cause {}", &upstream.url, e);
+                Err(ProxyError::Timeout)
+            },
+            Err(e) => {
+                error!("Can not reach upstream {}, cause {}", &upstream.url, e);
+                Err(ProxyError::Unavailable)
+            }
+        }
+    }
+}
+
+
+/// Valida se o caminho pode ser casado e repassado como está.
+/// Segmentos `.` e `..`, literais ou codificados, e segmentos vazios seriam
+/// resolvidos pelo cliente HTTP, escapando do prefixo da rota e do `host`.
+/// Apenas a barra final é aceita.
+pub fn is_safe_path(path: &str) -> bool {
+    let segments = match path.strip_prefix('/') {
+        Some(value) => value.split('/').collect::<Vec<_>>(),
+        None => return false,
+    };
+    let last = segments.len() - 1;
+
+    // A barra invertida é tratada como `/` nas URLs HTTP.
+    !path.contains('\\') && segments.iter().enumerate().all(|(index, segment)| {
+        let decoded = segment.to_ascii_lowercase().replace("%2e", ".");
+
+        decoded != "." && decoded != ".." && (index == last || !segment.is_empty())
+    })
+}
+
+
+/// Valida se o prefixo de rota atende o caminho.
+fn owns_path(route: &str, path: &str) -> bool {
+    match path.strip_prefix(route) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+
+/// Valida se o cabeçalho pode ser repassado entre cliente e micro serviço.
+pub fn is_forwardable(name: &str) -> bool {
+    !HOP_BY_HOP.iter().any(|header| name.eq_ignore_ascii_case(header))
+}
+
+
+/// Converte um par de cabeçalho para os tipos do cliente HTTP.
+pub fn to_header(name: &str, value: &[u8]) -> Option<(HeaderName, HeaderValue)> {
+    let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+    let value = HeaderValue::from_bytes(value).ok()?;
+
+    Some((name, value))
+}
@@ -0,0 +1,94 @@
+use bson::oid::ObjectId;
+use log::{debug, info};
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::services::tokens::TokenService;
+use crate::models::sessions::SessionModel;
+use crate::settings::Settings;
+
+
+#[derive(Clone)]
+pub struct SessionService{
+    storage: Storage,
+    tokens: TokenService,
+} impl SessionService {
+    pub fn new(storage: Storage) -> Self {
+        SessionService {
+            tokens: TokenService::new(storage.clone()),
+            storage,
+        }
+    }
+
+    /// Registra a sessão de um novo login.
+    pub async fn create(&self, session: &SessionModel) -> Result<(), AppError> {
+        self.storage.sessions.insert(session).await?;
+
+        debug!("Created session {} for user {}.", session._id, session.user);
+        Ok(())
+    }
+
+    /// Captura a sessão pelo ID.
+    pub async fn get(&self, id: &ObjectId) -> Result<Option<SessionModel>, AppError> {
+        self.storage.sessions.find_by_id(id).await
+    }
+
+    /// Lista as sessões do usuário, das mais recentes para as mais antigas.
+    pub async fn list(&self, user: &ObjectId) -> Result<Vec<SessionModel>, AppError> {
+        self.storage.sessions.list(user).await
+    }
+
+    /// Associa o novo token de acesso à sessão após a renovação.
+    /// Retorna falso se a sessão não existir mais.
+    pub async fn renew(&self, id: &ObjectId, jti: &str, token_expires_at: DateTime) -> Result<bool, AppError> {
+        let settings = Settings::get();
+        let now = DateTime::now();
+        let ttl = (settings.refresh_token_ttl * 1000) as i64;
+        let expires_at = DateTime::from_millis(now.timestamp_millis() + ttl);
+
+        self.storage.sessions.renew(id, jti, token_expires_at, now, expires_at).await
+    }
+
+    /// Atualiza o último acesso da sessão.
+    pub async fn touch(&self, id: &ObjectId) {
+        if self.storage.sessions.touch(id, DateTime::now()).await.is_ok() {
+            debug!("Touched session {}.", id);
+        }
+    }
+
+    /// Encerra a sessão: revoga o token de acesso vigente,
+    /// os tokens de renovação e remove o registro.
+    /// Falha apenas se o token de acesso não puder ser revogado.
+    pub async fn terminate(&self, session: &SessionModel) -> Result<(), AppError> {
+        let revoked = self.tokens
+            .revoke(&session.user, &session.jti, session.token_expires_at)
+            .await;
+
+        self.tokens.revoke_family(&session._id).await;
+
+        if self.storage.sessions.delete(&session._id).await.is_ok() {
+            info!("Terminated session {} of user {}.", session._id, session.user);
+        }
+
+        revoked
+    }
+
+    /// Encerra todas as sessões do usuário.
+    /// Tenta encerrar todas, mesmo que alguma falhe, e retorna a primeira falha.
+    pub async fn terminate_all(&self, user: &ObjectId) -> Result<(), AppError> {
+        let sessions = self.list(user).await?;
+        let mut result = Ok(());
+
+        for session in sessions.iter() {
+            if let Err(e) = self.terminate(session).await {
+                result = result.and(Err(e));
+            }
+        }
+
+        // Tokens de renovação sem sessão, emitidos antes das sessões existirem.
+        self.tokens.revoke_refresh_tokens(user).await;
+
+        result
+    }
+}
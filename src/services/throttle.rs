@@ -0,0 +1,156 @@
+use log::{debug, warn};
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::settings::Settings;
+
+
+/// Chave do contador de falhas por usuário.
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username)
+}
+
+
+/// Chave do contador de falhas por IP.
+pub fn ip_key(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+
+#[derive(Clone)]
+pub struct ThrottleService{
+    storage: Storage,
+} impl ThrottleService {
+    pub fn new(storage: Storage) -> Self {
+        ThrottleService {
+            storage,
+        }
+    }
+
+    /// Valida se as chaves podem tentar um novo login.
+    /// Quando bloqueadas, retorna os segundos até a próxima tentativa.
+    pub async fn check(&self, keys: &[String]) -> Result<(), u64> {
+        let now = DateTime::now();
+        let attempt = match self.storage.login_attempts.find_blocked(keys, now).await {
+            Ok(value) => value,
+            // Falha no banco não deve impedir o login.
+            Err(_) => return Ok(()),
+        };
+
+        match attempt {
+            Some(value) => {
+                let millis = value.blocked_until.timestamp_millis() - now.timestamp_millis();
+                // Arredonda para cima, nunca menos de um segundo.
+                Err(((millis + 999) / 1000).max(1) as u64)
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Conta uma falha de login e, com `backoff`, bloqueia a chave com espera exponencial.
+    /// Ao atingir `max_failures`, bloqueia pelo tempo de `login_lockout`.
+    /// Chaves de IP, compartilhadas e nunca zeradas no sucesso, recebem apenas o bloqueio.
+    pub async fn record_failure(&self, key: &str, max_failures: u32, backoff: bool) {
+        let settings = Settings::get();
+        let now = DateTime::now();
+        let mut attempt = match self.storage.login_attempts.record_failure(key, now).await {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+
+        // Terminado o bloqueio, só uma nova sequência de `max_failures` bloqueia de novo.
+        if attempt.failures > max_failures && attempt.blocked_until <= now {
+            debug!("Lockout of {} ended, restarting its failures.", key);
+
+            let restarted = match self.storage.login_attempts.delete(key).await {
+                Ok(_) => self.storage.login_attempts.record_failure(key, now).await,
+                Err(e) => Err(e),
+            };
+            attempt = match restarted {
+                Ok(value) => value,
+                Err(_) => return,
+            };
+        }
+
+        let delay = if attempt.failures >= max_failures {
+            if attempt.failures == max_failures {
+                warn!("Locking {} after {} login failures.", key, attempt.failures);
+            }
+            settings.login_lockout
+        } else if backoff {
+            1u64.checked_shl(attempt.failures.saturating_sub(1))
+                .unwrap_or(u64::MAX)
+                .min(settings.login_backoff_max)
+        } else {
+            0
+        };
+        // Valores enormes saturam, nunca voltam ao passado e desligam o bloqueio.
+        let millis = |seconds: u64| i64::try_from(seconds.saturating_mul(1000)).unwrap_or(i64::MAX);
+        let blocked_until = now.timestamp_millis().saturating_add(millis(delay));
+        let expires_at = blocked_until.max(now.timestamp_millis().saturating_add(millis(settings.login_failure_ttl)));
+        let blocked_until = DateTime::from_millis(blocked_until);
+        let expires_at = DateTime::from_millis(expires_at);
+
+        if self.storage.login_attempts.block(&attempt._id, blocked_until, expires_at).await.is_ok() && delay > 0 {
+            debug!("Blocked {} for {} seconds.", key, delay);
+        }
+    }
+
+    /// Descarta o contador de falhas da chave.
+    pub async fn reset(&self, key: &str) -> Result<(), AppError> {
+        if self.storage.login_attempts.delete(key).await? {
+            debug!("Reset login failures of {}.", key);
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    async fn blocked_for(service: &ThrottleService, key: &str) -> Option<u64> {
+        service.check(&[key.to_string()]).await.err()
+    }
+
+
+    #[actix_web::test]
+    async fn lockout_restarts_the_failures_when_it_ends() {
+        let storage = Storage::memory();
+        let service = ThrottleService::new(storage.clone());
+        let settings = Settings::get();
+        let key = user_key("victim");
+
+        for _ in 0..3 {
+            service.record_failure(&key, 3, true).await;
+        }
+        assert_eq!(blocked_for(&service, &key).await, Some(settings.login_lockout));
+
+        // Simula o fim do bloqueio, sem esperar o `login_lockout`.
+        let attempt = storage.login_attempts.find_blocked(std::slice::from_ref(&key), DateTime::now()).await.unwrap().unwrap();
+        let ended = DateTime::from_millis(DateTime::now().timestamp_millis() - 1000);
+        storage.login_attempts.block(&attempt._id, ended, attempt.expires_at).await.unwrap();
+        assert_eq!(blocked_for(&service, &key).await, None);
+
+        // A próxima falha volta à primeira espera, não a um novo bloqueio.
+        service.record_failure(&key, 3, true).await;
+        assert_eq!(blocked_for(&service, &key).await, Some(1));
+    }
+
+
+    #[actix_web::test]
+    async fn ip_keys_are_only_locked_out() {
+        let service = ThrottleService::new(Storage::memory());
+        let key = ip_key("10.0.0.1");
+
+        service.record_failure(&key, 2, false).await;
+        assert_eq!(blocked_for(&service, &key).await, None);
+
+        service.record_failure(&key, 2, false).await;
+        assert_eq!(blocked_for(&service, &key).await, Some(Settings::get().login_lockout));
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+
+use bson::oid::ObjectId;
+use log::{debug, error, info, warn};
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::tokens::{RefreshTokenModel, RevokedTokenModel};
+use crate::services::sessions::SessionService;
+use crate::settings::Settings;
+use crate::tools::hasher;
+
+
+/// Motivos pelos quais a renovação do token pode falhar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshError {
+    /// Token desconhecido ou revogado.
+    Invalid,
+    /// Token vencido.
+    Expired,
+    /// Token já trocado anteriormente, a família foi revogada.
+    Reused,
+    /// Falha ao consultar ou gravar no banco de dados.
+    Storage,
+} impl fmt::Display for RefreshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            RefreshError::Invalid => "invalid refresh token",
+            RefreshError::Expired => "refresh token has expired",
+            RefreshError::Reused => "refresh token reuse detected",
+            RefreshError::Storage => "can not access refresh tokens",
+        };
+
+        write!(f, "{}", reason)
+    }
+} impl From<RefreshError> for AppError {
+    fn from(error: RefreshError) -> Self {
+        let code = match error {
+            RefreshError::Invalid => "invalid_refresh_token",
+            RefreshError::Expired => "refresh_token_expired",
+            RefreshError::Reused => "refresh_token_reused",
+            RefreshError::Storage => return AppError::Database,
+        };
+
+        AppError::Unauthorized {
+            code,
+            detail: error.to_string(),
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct TokenService{
+    storage: Storage,
+} impl TokenService {
+    pub fn new(storage: Storage) -> Self {
+        TokenService {
+            storage,
+        }
+    }
+
+    /// Emite um novo token de renovação para o usuário.
+    /// Sem família informada, inicia uma nova família de tokens.
+    pub async fn issue(&self, user: &ObjectId, family: Option<ObjectId>) -> Result<String, AppError> {
+        let settings = Settings::get();
+        let token = hasher::generate_refresh_token();
+        let now = DateTime::now();
+        let ttl = (settings.refresh_token_ttl * 1000) as i64;
+        let model = RefreshTokenModel {
+            _id: ObjectId::new(),
+            user: *user,
+            family: family.unwrap_or_default(),
+            token_hash: hasher::hash_refresh_token(&token),
+            created_at: now,
+            expires_at: DateTime::from_millis(now.timestamp_millis() + ttl),
+            rotated_at: None,
+            revoked: false,
+        };
+
+        self.storage.tokens.insert_refresh(&model).await?;
+
+        debug!("Issued refresh token for user {}.", user);
+        Ok(token)
+    }
+
+    /// Troca um token de renovação por um novo da mesma família.
+    /// Retorna o usuário dono do token, a família e o novo token opaco.
+    pub async fn rotate(&self, token: &str) -> Result<(ObjectId, ObjectId, String), RefreshError> {
+        let token_hash = hasher::hash_refresh_token(token);
+        let current = match self.storage.tokens.find_refresh(&token_hash).await {
+            Ok(Some(value)) => value,
+            Ok(None) => return Err(RefreshError::Invalid),
+            Err(_) => return Err(RefreshError::Storage),
+        };
+
+        if current.revoked {
+            return Err(RefreshError::Invalid);
+        }
+        if current.rotated_at.is_some() {
+            warn!("Refresh token reuse detected for user {}.", current.user);
+            self.terminate_family(&current).await;
+            return Err(RefreshError::Reused);
+        }
+        if current.expires_at <= DateTime::now() {
+            return Err(RefreshError::Expired);
+        }
+
+        // Marca como trocado apenas se ninguém o fez antes.
+        match self.storage.tokens.mark_rotated(&current._id, DateTime::now()).await {
+            Ok(true) => (),
+            Ok(false) => {
+                warn!("Concurrent refresh token reuse for user {}.", current.user);
+                self.terminate_family(&current).await;
+                return Err(RefreshError::Reused);
+            },
+            Err(_) => return Err(RefreshError::Storage),
+        };
+
+        match self.issue(&current.user, Some(current.family)).await {
+            Ok(value) => Ok((current.user, current.family, value)),
+            Err(_) => Err(RefreshError::Storage),
+        }
+    }
+
+    /// Reage ao reuso de um token de renovação.
+    /// A família é a sessão: encerrá-la revoga também o token de acesso vigente,
+    /// que pode estar com quem roubou o token.
+    async fn terminate_family(&self, current: &RefreshTokenModel) {
+        let sessions = SessionService::new(self.storage.clone());
+        match sessions.get(&current.family).await.unwrap_or_default() {
+            Some(value) => {
+                if let Err(e) = sessions.terminate(&value).await {
+                    error!("Can not terminate session {} after token reuse. Cause: {}", value._id, e);
+                }
+            },
+            // Tokens emitidos antes das sessões existirem.
+            None => self.revoke_family(&current.family).await,
+        }
+    }
+
+    /// Revoga todos os tokens de uma família.
+    pub async fn revoke_family(&self, family: &ObjectId) {
+        if let Ok(count) = self.storage.tokens.revoke_family(family).await {
+            info!("Revoked {} refresh tokens of family {}.", count, family);
+        }
+    }
+
+    /// Revoga todos os tokens de renovação do usuário.
+    pub async fn revoke_refresh_tokens(&self, user: &ObjectId) {
+        if let Ok(count) = self.storage.tokens.revoke_user(user).await {
+            info!("Revoked {} refresh tokens of user {}.", count, user);
+        }
+    }
+
+    /// Inclui o token de acesso na lista de revogados até seu vencimento.
+    pub async fn revoke(&self, user: &ObjectId, jti: &str, expires_at: DateTime) -> Result<(), AppError> {
+        let model = RevokedTokenModel {
+            _id: ObjectId::new(),
+            jti: jti.to_string(),
+            user: *user,
+            revoked_at: DateTime::now(),
+            expires_at,
+        };
+
+        self.storage.tokens.insert_revoked(&model).await?;
+
+        info!("Revoked access token {} of user {}.", jti, user);
+        Ok(())
+    }
+
+    /// Valida se o token de acesso foi revogado.
+    pub async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        self.storage.tokens.is_revoked(jti).await
+    }
+}
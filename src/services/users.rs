@@ -1,76 +1,96 @@
-use bson::de;
 use bson::oid::ObjectId;
 use log::{debug, info, error};
-use mongodb::bson::{Document, doc};
-use futures_util::stream::TryStreamExt;
+use mongodb::bson::DateTime;
 
-use crate::services::MongoService;
-use crate::models::users::{UserModel, UserSerialize};
+use crate::errors::AppError;
+use crate::repositories::Storage;
+use crate::models::users::{UserChanges, UserModel, UserSerialize};
 
+
+#[derive(Clone)]
 pub struct UserService{
-    service: MongoService,
+    storage: Storage,
 } impl UserService {
-    pub fn new(service: MongoService) -> Self {
+    pub fn new(storage: Storage) -> Self {
         UserService {
-            service,
+            storage,
         }
     }
 
     /// Captura um usuário pelo username.
-    pub async fn get_by_username(&self, username: &String) -> Option<UserModel> {
-        let data = self.service
-            .user_model
-            .find_one(doc!{"username": username})
-            .await;
-
-        match data {
-            Ok(value) => {
-                debug!("Get user {} in database.", username);
-                value
-            },
-            Err(e) => {
-                error!("Can not filter {} in users, cause {}", username, e);
-                None
-            }
-        }
+    pub async fn get_by_username(&self, username: &String) -> Result<Option<UserModel>, AppError> {
+        let user = self.storage.users.find_by_username(username).await?;
+
+        debug!("Get user {} in database.", username);
+        Ok(user)
     }
 
     /// Captura o usuário pelo ID
-    pub async fn get_by_id(&self, id: &ObjectId) -> Option<UserSerialize> {
-        let data = self.service
-            .user_serialize
-            .find_one(doc!{"_id": id})
-            .await;
-
-        match data {
-            Ok(user) => {
-                debug!("Try to get user {} in database.", id);
-                user
-            },
-            Err(e) => {
-                error!("Can not filter {} in users, cause {}.", id, e);
-                None
+    pub async fn get_by_id(&self, id: &ObjectId) -> Result<Option<UserSerialize>, AppError> {
+        let user = self.get_model_by_id(id).await?;
+
+        Ok(user.as_ref().map(UserSerialize::from))
+    }
+
+    /// Captura o usuário completo pelo ID.
+    pub async fn get_model_by_id(&self, id: &ObjectId) -> Result<Option<UserModel>, AppError> {
+        let user = self.storage.users.find_by_id(id).await?;
+
+        debug!("Try to get user {} in database.", id);
+        Ok(user)
+    }
+
+    /// Lista os usuários, dos mais recentes para os mais antigos.
+    pub async fn list(&self, is_active: Option<bool>, skip: u64, limit: i64) -> Result<Vec<UserSerialize>, AppError> {
+        let users = self.storage.users.list(is_active, skip, limit).await?;
+
+        Ok(users.iter().map(UserSerialize::from).collect())
+    }
+
+    /// Cadastra um novo usuário.
+    pub async fn create(&self, user: &UserModel) -> Result<(), AppError> {
+        self.storage.users.insert(user).await?;
+
+        info!("Created user {}.", &user.username);
+        Ok(())
+    }
+
+    /// Altera os campos informados do usuário.
+    /// Retorna o usuário já atualizado, se existir.
+    pub async fn update(&self, id: &ObjectId, changes: &UserChanges) -> Result<Option<UserSerialize>, AppError> {
+        let user = self.storage.users.update(id, changes).await?;
+
+        debug!("Updated user {}.", id);
+        Ok(user.as_ref().map(UserSerialize::from))
+    }
+
+    /// Remove o usuário e seus relacionamentos com grupos.
+    /// Retorna falso se o usuário não existir.
+    pub async fn delete(&self, id: &ObjectId) -> Result<bool, AppError> {
+        let deleted = self.storage.users.delete(id).await?;
+
+        if deleted {
+            info!("Deleted user {}.", id);
+
+            if self.storage.relationships.remove_user_everywhere(id).await.is_ok() {
+                debug!("Deleted groups relationship of user {}.", id);
             }
         }
+
+        Ok(deleted)
     }
 
-    /// Altera o token na coleção de usuários.
-    pub async fn set_token(&self, username: &String, token: &String) {
-        let query = doc!{
-            "username": username,
-        };
-        let update = doc!{
-            "$set": {
-                "token": token,
-            }
+    /// Altera o hash da senha do usuário.
+    pub async fn set_password(&self, username: &str, password: &str) {
+        match self.storage.users.set_password(username, password).await {
+            Ok(_) => debug!("Updated password of user {}", username),
+            Err(e) => error!("Can not update password of user {}, cause {}", username, e),
         };
+    }
 
-        match self.service
-            .user_model
-            .update_one(query, update)
-            .await {
-                Ok(_) => debug!("Updated user {}", username),
-                Err(e) => error!("Can not update user {}, cause {}", username, e),
-            };
+    /// Grava o instante do login se a conta ainda estiver ativa.
+    /// Retorna falso se a conta foi desativada.
+    pub async fn record_login(&self, id: &ObjectId, now: DateTime) -> Result<bool, AppError> {
+        self.storage.users.record_login(id, now).await
     }
 }
@@ -0,0 +1,317 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+
+/// Prefixo das variáveis de ambiente do serviço.
+pub const ENV_PREFIX: &str = "EASY_MDLWR_";
+/// Chaves ainda aceitas pelos nomes antigos, sem prefixo, das implantações existentes.
+const LEGACY_KEYS: &[&str] = &["mongo_uri", "mongo_db", "jwt_secret_key"];
+
+
+/// Origem de um valor de configuração, usada nas mensagens de erro.
+#[derive(Debug, Clone)]
+pub enum Origin {
+    File(String),
+    Env(String),
+    Cli(String),
+} impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "file {}", path),
+            Origin::Env(name) => write!(f, "environment variable `{}`", name),
+            Origin::Cli(flag) => write!(f, "flag `--{}`", flag),
+        }
+    }
+}
+
+
+/// Valores aceitos no arquivo de configuração, TOML ou YAML.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FileValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    List(Vec<FileValue>),
+    Table(BTreeMap<String, FileValue>),
+}
+
+
+/// Valores crus de configuração já com a precedência aplicada:
+/// arquivo, depois ambiente, depois linha de comando.
+#[derive(Debug, Default)]
+pub struct Sources {
+    values: HashMap<String, (String, Origin)>,
+} impl Sources {
+    /// Junta as fontes de configuração para as chaves conhecidas.
+    /// O ambiente é informado por `vars`, em geral o do processo, por `process_env`.
+    /// As opções da linha de comando terminam no primeiro argumento posicional;
+    /// ele e os seguintes são retornados, como os subcomandos.
+    pub fn collect<I>(keys: &[&str], args: &[String], vars: I) -> Result<(Sources, Vec<String>), String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let (flags, rest) = parse_flags(args)?;
+        let mut sources = Sources::default();
+        // Variáveis vazias equivalem às ausentes.
+        let vars: HashMap<String, String> = vars
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        let env_value = |name: &str| vars.get(name).cloned();
+
+        // Arquivo indicado por `--config` ou pelo ambiente.
+        let config = flags
+            .iter()
+            .find(|(name, _)| name == "config")
+            .map(|(_, value)| value.clone())
+            .or_else(|| env_value(&format!("{}CONFIG", ENV_PREFIX)));
+
+        if let Some(path) = config {
+            for (key, value) in read_file(&path)? {
+                if !keys.contains(&key.as_str()) {
+                    return Err(format!("unknown setting `{}` in file {}", key, path));
+                }
+                sources.values.insert(key, (value, Origin::File(path.clone())));
+            }
+        }
+
+        // Variáveis com prefixo têm precedência sobre os nomes antigos, sem prefixo.
+        for key in keys.iter() {
+            let name = key.to_ascii_uppercase();
+            let prefixed = format!("{}{}", ENV_PREFIX, name);
+            let names = match LEGACY_KEYS.contains(key) {
+                true => vec![name, prefixed],
+                false => vec![prefixed],
+            };
+
+            for name in names {
+                if let Some(value) = env_value(&name) {
+                    sources.values.insert(key.to_string(), (value, Origin::Env(name)));
+                }
+            }
+        }
+
+        for (name, value) in flags.into_iter().filter(|(name, _)| name != "config") {
+            let key = name.replace('-', "_");
+
+            if !keys.contains(&key.as_str()) {
+                return Err(format!("unknown flag `--{}`", name));
+            }
+            sources.values.insert(key, (value, Origin::Cli(name)));
+        }
+
+        Ok((sources, rest))
+    }
+
+    /// Valor cru e origem da chave, se informada em alguma fonte.
+    pub fn get(&self, key: &str) -> Option<&(String, Origin)> {
+        self.values.get(key)
+    }
+}
+
+
+/// Variáveis do processo, ignorando as que não são UTF-8.
+pub fn process_env() -> impl Iterator<Item = (String, String)> {
+    env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+}
+
+
+/// Opções `--nome valor` na ordem em que foram informadas.
+type Flags = Vec<(String, String)>;
+
+
+/// Separa as opções `--nome valor` ou `--nome=valor` dos argumentos posicionais.
+fn parse_flags(args: &[String]) -> Result<(Flags, Vec<String>), String> {
+    let mut flags = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let name = match arg.strip_prefix("--") {
+            Some(value) => value,
+            None => {
+                let mut rest = vec![arg.clone()];
+                rest.extend(iter.cloned());
+                return Ok((flags, rest));
+            }
+        };
+
+        match name.split_once('=') {
+            Some((name, value)) => flags.push((name.to_string(), value.to_string())),
+            None => match iter.next() {
+                Some(value) => flags.push((name.to_string(), value.clone())),
+                None => return Err(format!("missing value for flag `--{}`", name)),
+            },
+        }
+    }
+
+    Ok((flags, Vec::new()))
+}
+
+
+/// Lê o arquivo de configuração conforme a extensão.
+/// Tabelas aninhadas viram chaves com `_`: `[jwt] ttl = 900` é `jwt_ttl`.
+fn read_file(path: &str) -> Result<Vec<(String, String)>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("can not read config file {}: {}", path, e))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+
+    let table: BTreeMap<String, FileValue> = match extension {
+        "toml" => toml::from_str(&content)
+            .map_err(|e| format!("invalid TOML in {}: {}", path, e))?,
+        "yaml" | "yml" => serde_yaml::from_str(&content)
+            .map_err(|e| format!("invalid YAML in {}: {}", path, e))?,
+        _ => return Err(format!("config file {} must be .toml, .yaml or .yml", path)),
+    };
+
+    let mut values = Vec::new();
+    flatten("", table, &mut values)
+        .map_err(|key| format!("invalid value for `{}` in {}", key, path))?;
+
+    Ok(values)
+}
+
+
+/// Achata as tabelas do arquivo. Listas viram valores separados por vírgula.
+fn flatten(prefix: &str, table: BTreeMap<String, FileValue>, values: &mut Vec<(String, String)>) -> Result<(), String> {
+    for (name, value) in table {
+        let key = match prefix {
+            "" => name,
+            _ => format!("{}_{}", prefix, name),
+        };
+
+        match value {
+            FileValue::Table(inner) => flatten(&key, inner, values)?,
+            FileValue::List(items) => {
+                let mut parts = Vec::new();
+
+                for item in items {
+                    match scalar(item) {
+                        Some(part) => parts.push(part),
+                        None => return Err(key),
+                    }
+                }
+                values.push((key, parts.join(",")));
+            },
+            other => match scalar(other) {
+                Some(part) => values.push((key, part)),
+                None => return Err(key),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+
+fn scalar(value: FileValue) -> Option<String> {
+    match value {
+        FileValue::Bool(value) => Some(value.to_string()),
+        FileValue::Integer(value) => Some(value.to_string()),
+        FileValue::Float(value) => Some(value.to_string()),
+        FileValue::Text(value) => Some(value),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    /// Cada teste usa um arquivo próprio, já que rodam em paralelo.
+    fn write_config(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(format!("easy_mdlwr-{}-{}.toml", name, std::process::id()));
+
+        fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+
+    fn vars(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+
+    fn value(sources: &Sources, key: &str) -> String {
+        sources.get(key).map(|(value, _)| value.clone()).unwrap_or_default()
+    }
+
+
+    #[test]
+    fn cli_overrides_env_which_overrides_file() {
+        let keys = ["precedence_file", "precedence_env", "precedence_cli"];
+        let config = write_config("precedence", "[precedence]\nfile = \"file\"\nenv = \"file\"\ncli = \"file\"\n");
+
+        let env = vars(&[("EASY_MDLWR_PRECEDENCE_ENV", "env"), ("EASY_MDLWR_PRECEDENCE_CLI", "env")]);
+
+        let (sources, rest) = Sources::collect(
+            &keys,
+            &args(&["--config", &config, "--precedence-cli", "cli", "keys", "list"]),
+            env,
+        ).unwrap();
+
+        assert_eq!(value(&sources, "precedence_file"), "file");
+        assert_eq!(value(&sources, "precedence_env"), "env");
+        assert_eq!(value(&sources, "precedence_cli"), "cli");
+        assert!(matches!(sources.get("precedence_cli"), Some((_, Origin::Cli(_)))));
+        assert_eq!(rest, args(&["keys", "list"]));
+    }
+
+
+    #[test]
+    fn nested_tables_are_flattened() {
+        let keys = ["flatten_jwt_ttl", "flatten_cors_origins", "flatten_enabled"];
+        let config = write_config(
+            "flatten",
+            "[flatten]\nenabled = true\n[flatten.jwt]\nttl = 900\n[flatten.cors]\norigins = [\"https://a.example\", \"https://b.example\"]\n",
+        );
+
+        let (sources, _) = Sources::collect(&keys, &args(&["--config", &config]), Vec::new()).unwrap();
+
+        assert_eq!(value(&sources, "flatten_jwt_ttl"), "900");
+        assert_eq!(value(&sources, "flatten_enabled"), "true");
+        assert_eq!(value(&sources, "flatten_cors_origins"), "https://a.example,https://b.example");
+    }
+
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let keys = ["unknown_known"];
+        let config = write_config("unknown", "[unknown]\nknown = 1\ntypo = 2\n");
+
+        let error = Sources::collect(&keys, &args(&["--config", &config]), Vec::new()).unwrap_err();
+        assert!(error.contains("unknown setting `unknown_typo`"), "{}", error);
+
+        let error = Sources::collect(&keys, &args(&["--unknown-typo", "1"]), Vec::new()).unwrap_err();
+        assert_eq!(error, "unknown flag `--unknown-typo`");
+    }
+
+
+    #[test]
+    fn only_legacy_keys_read_unprefixed_env() {
+        let keys = ["mongo_db", "legacy_ttl"];
+
+        let env = vars(&[("MONGO_DB", "legacy"), ("LEGACY_TTL", "60")]);
+
+        let (sources, _) = Sources::collect(&keys, &[], env).unwrap();
+
+        assert_eq!(value(&sources, "mongo_db"), "legacy");
+        assert!(sources.get("legacy_ttl").is_none());
+    }
+}
@@ -1,40 +1,58 @@
 
-use std::str;
-use std::io::Read;
+use std::fmt;
+use std::sync::OnceLock;
 use std::iter::Iterator;
-use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use log::error;
-use sha2::{Sha512, Digest, Sha384};
+use log::{debug, error, warn};
+use sha2::{Sha256, Sha512, Digest};
+use subtle::ConstantTimeEq;
 use hmac::{Hmac, Mac};
-use jwt::{AlgorithmType, Header, SignWithKey, Token, VerifyWithKey};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::SaltString;
+use rand_core::{OsRng, RngCore};
+use uuid::Uuid;
+use bson::oid::ObjectId;
 
 
+use crate::errors::AppError;
+use crate::models::tokens::{Claims, InternalClaims};
 use crate::models::users::UserModel;
 use crate::settings::Settings;
+use crate::tools::keys;
 
 
-/// Encripta uma senha.
+/// Monta o Argon2id com os custos definidos nas configurações.
+fn argon2_instance() -> Option<Argon2<'static>> {
+    // Captura informações de configuração.
+    let settings = Settings::get();
+    let params = match Params::new(
+        settings.argon2_memory_cost,
+        settings.argon2_time_cost,
+        settings.argon2_parallelism,
+        None,
+    ) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Invalid Argon2 parameters. Cause: {}", e);
+            return None;
+        }
+    };
+
+    Some(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+
+/// Encripta uma senha com Argon2id e salt aleatório.
+/// O retorno é uma string no formato PHC.
 pub fn hash_password(password: &str) -> Option<String>{
-    // Instância um objeto de hash.
-    let mut hasher = Sha512::new();
-    hasher.update(password.as_bytes());
-    let result = hasher
-        .finalize();
-    let result: Result<Vec<_>, _> = result
-        .bytes()
-        .collect();
-
-    match result {
-        Ok(value) =>{
-            match str::from_utf8(&value) {
-                Ok(data) => Some(data.to_owned()),
-                Err(e) => {
-                    error!("Can not make hash from password. Cause: {}", e);
-                    None
-                }
-            }
-        },
+    let argon2 = argon2_instance()?;
+    let salt = SaltString::generate(&mut OsRng);
+
+    match argon2.hash_password(password.as_bytes(), &salt) {
+        Ok(value) => Some(value.to_string()),
         Err(e) => {
             error!("Can not make hash from password. Cause: {}", e);
             None
@@ -44,81 +62,476 @@ pub fn hash_password(password: &str) -> Option<String>{
 
 
 /// Valida se a senha está correta.
+/// Aceita hashes PHC e, para migração, o antigo SHA-512 sem salt.
+/// A comparação do Argon2 já é feita em tempo constante.
 pub fn is_valid_password(password: &str, hash: &str) -> bool {
-    let checker = match hash_password(password) {
-        Some(value) => value,
-        None => {
-            return false;
+    let parsed = match PasswordHash::new(hash) {
+        Ok(value) => value,
+        Err(_) => {
+            return is_valid_legacy_password(password, hash.as_bytes());
         }
     };
 
-    if checker != hash {
-        return false;
+    // A verificação usa os parâmetros gravados no próprio hash.
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+}
+
+
+/// Valida se o hash precisa ser refeito com os parâmetros atuais.
+pub fn needs_rehash(hash: &str) -> bool {
+    let parsed = match PasswordHash::new(hash) {
+        Ok(value) => value,
+        Err(_) => return true,
+    };
+
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    let current = match Params::try_from(&parsed) {
+        Ok(value) => value,
+        Err(_) => return true,
+    };
+    let settings = Settings::get();
+
+    current.m_cost() != settings.argon2_memory_cost
+        || current.t_cost() != settings.argon2_time_cost
+        || current.p_cost() != settings.argon2_parallelism
+}
+
+
+/// Valida senhas gravadas no formato legado, SHA-512 sem salt.
+/// O hash pode estar em hexadecimal ou nos bytes crus do digest.
+fn is_valid_legacy_password(password: &str, hash: &[u8]) -> bool {
+    let mut hasher = Sha512::new();
+    hasher.update(password.as_bytes());
+    let digest = hasher.finalize();
+
+    // Compara as duas representações sempre, sem atalhos.
+    let hex_matches = secure_eq(to_hex(&digest).as_bytes(), &hash.to_ascii_lowercase());
+    let raw_matches = secure_eq(&digest, hash);
+
+    if hex_matches | raw_matches {
+        warn!("Legacy password hash matched, it must be upgraded.");
+    }
+
+    hex_matches | raw_matches
+}
+
+
+/// Executa uma verificação Argon2id descartável.
+/// Usada quando o usuário não existe, para o tempo de resposta não revelar isso.
+pub fn dummy_verify_password(password: &str) {
+    static DUMMY: OnceLock<Option<String>> = OnceLock::new();
+
+    let hash = DUMMY.get_or_init(|| hash_password("dummy-password-for-timing"));
+
+    if let Some(value) = hash {
+        is_valid_password(password, value);
+    }
+}
+
+
+/// Compara segredos em tempo constante.
+/// Apenas o tamanho das entradas pode ser inferido pelo tempo.
+pub fn secure_eq(left: &[u8], right: &[u8]) -> bool {
+    left.ct_eq(right).into()
+}
+
+
+/// Motivos pelos quais um token JWT pode ser recusado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// Não foi possível montar a chave de verificação.
+    Key,
+    /// O token foi assinado por uma chave desconhecida.
+    UnknownKey,
+    /// O token não está no formato JWT esperado.
+    Malformed,
+    /// O algoritmo do cabeçalho não é o configurado.
+    Algorithm,
+    /// A assinatura não confere.
+    Signature,
+    /// O token já expirou.
+    Expired,
+    /// O token ainda não é válido.
+    NotYetValid,
+    /// O token foi emitido por outro emissor.
+    Issuer,
+    /// O token foi emitido para outro destinatário.
+    Audience,
+} impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            TokenError::Key => "signing key unavailable",
+            TokenError::UnknownKey => "unknown signing key",
+            TokenError::Malformed => "malformed token",
+            TokenError::Algorithm => "unexpected signing algorithm",
+            TokenError::Signature => "invalid signature",
+            TokenError::Expired => "token has expired",
+            TokenError::NotYetValid => "token is not valid yet",
+            TokenError::Issuer => "invalid issuer",
+            TokenError::Audience => "invalid audience",
+        };
+
+        write!(f, "{}", reason)
     }
+} impl From<TokenError> for AppError {
+    fn from(error: TokenError) -> Self {
+        match error {
+            TokenError::Key => AppError::Internal("can not validate access token"),
+            TokenError::Expired => AppError::Unauthorized {
+                code: "token_expired",
+                detail: error.to_string(),
+            },
+            _ => AppError::invalid_token(&error.to_string()),
+        }
+    }
+} impl std::error::Error for TokenError {}
+
 
-    true
+/// Segundos desde a época Unix.
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
 }
 
 
-/// Gera um token JWT.
-pub fn generate_jtw(user: &UserModel) -> Option<String> {
+/// Gera um token JWT para a sessão informada.
+/// O cabeçalho leva o `kid` da chave ativa.
+/// Retorna o token junto das claims assinadas.
+pub fn generate_jtw(user: &UserModel, session: &ObjectId) -> Option<(String, Claims)> {
     // Captura informações de configuração.
-    let settings = Settings::load();
-    // Transforma o chave em bytes.
-    let secret: &[u8] = &settings.jwt_secret_key.into_bytes();
-    // Gera uma chave Hmac.
-    let key: Hmac<Sha384> = match Hmac::new_from_slice(secret) {
-        Ok(value) => value,
+    let settings = Settings::get();
+    let key = keys::active()?;
+    let mut header = Header::new(key.algorithm);
+
+    header.kid = Some(key.kid.clone());
+
+    let now = now_timestamp();
+    let claims = Claims {
+        sub: user._id.to_hex(),
+        username: user.username.clone(),
+        email: user.email.clone(),
+        iss: settings.jwt_issuer.clone(),
+        aud: settings.jwt_audience.clone(),
+        exp: now + settings.jwt_ttl,
+        iat: now,
+        nbf: now,
+        jti: Uuid::new_v4().to_string(),
+        sid: session.to_hex(),
+    };
+
+    match encode(&header, &claims, &key.encoding) {
+        Ok(value) => Some((value, claims)),
         Err(e) => {
             error!("Can not generate JWT. Cause: {}", e);
-            return None;
+            None
         },
+    }
+}
+
+
+/// Valida e desencripta o token.
+/// A chave é escolhida pelo `kid` do cabeçalho dentre as do chaveiro.
+/// Recusa tokens expirados, ainda não válidos ou de outro emissor/destinatário.
+pub fn decode_jtw(token: String) -> Result<Claims, TokenError> {
+    // Captura informações de configuração.
+    let settings = Settings::get();
+    let header = decode_header(&token).map_err(|_| TokenError::Malformed)?;
+
+    keys::active().ok_or(TokenError::Key)?;
+
+    // Seleciona a chave pelo `kid` do cabeçalho, ativa ou apenas de validação.
+    let key = header.kid
+        .as_deref()
+        .and_then(keys::find)
+        .ok_or(TokenError::UnknownKey)?;
+
+    if header.alg != key.algorithm {
+        return Err(TokenError::Algorithm);
+    }
+
+    let mut validation = Validation::new(key.algorithm);
+
+    validation.leeway = 0;
+    validation.validate_nbf = true;
+    validation.set_issuer(&[&settings.jwt_issuer]);
+    validation.set_audience(&[&settings.jwt_audience]);
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+
+    match decode::<Claims>(&token, &key.decoding, &validation) {
+        Ok(data) => Ok(data.claims),
+        Err(e) => Err(match e.kind() {
+            ErrorKind::InvalidSignature => TokenError::Signature,
+            ErrorKind::InvalidAlgorithm => TokenError::Algorithm,
+            ErrorKind::ExpiredSignature => TokenError::Expired,
+            ErrorKind::ImmatureSignature => TokenError::NotYetValid,
+            ErrorKind::InvalidIssuer => TokenError::Issuer,
+            ErrorKind::InvalidAudience => TokenError::Audience,
+            _ => {
+                debug!("Can not decode JTW. Cause: {}", e);
+                TokenError::Malformed
+            }
+        }),
+    }
+}
+
+
+/// Gera o token interno enviado a um micro serviço.
+/// É assinado pela chave interna assimétrica, com o `kid` publicado no JWKS.
+/// Retorna `None` quando não há chave interna configurada.
+pub fn generate_internal_jwt(user: &UserModel, audience: &str, permissions: Vec<String>) -> Option<String> {
+    // Captura informações de configuração.
+    let settings = Settings::get();
+    let key = keys::internal()?;
+    let mut header = Header::new(key.algorithm);
+
+    header.kid = Some(key.kid.clone());
+    let now = now_timestamp();
+    let claims = InternalClaims {
+        sub: user._id.to_hex(),
+        username: user.username.clone(),
+        iss: settings.internal_jwt_issuer.clone(),
+        aud: audience.to_string(),
+        exp: now + settings.internal_jwt_ttl,
+        iat: now,
+        nbf: now,
+        jti: Uuid::new_v4().to_string(),
+        permissions,
     };
-    let header = Header {
-        algorithm: AlgorithmType::Hs384,
-        ..Default::default()
-    };
-    let mut claims = BTreeMap::new();
-    claims.insert("username", user.username.clone());
-    claims.insert("email", user.email.clone());
-    let token = match Token::new(header, claims).sign_with_key(&key) {
-        Ok(value) => value,
+
+    match encode(&header, &claims, &key.encoding) {
+        Ok(value) => Some(value),
         Err(e) => {
-            error!("Can not generate JWT. Cause: {}", e);
-            return None;
+            error!("Can not generate internal JWT. Cause: {}", e);
+            None
         },
-    };
-    let token = token.as_str();
+    }
+}
 
-    Some(token.to_owned())
+
+/// Gera um token de renovação opaco com 256 bits aleatórios.
+pub fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    to_hex(&bytes)
 }
 
 
-/// Valida e desencripta o token.
-pub fn decode_jtw(token: String) -> Option<BTreeMap<String, String>>{
-    // Captura informações de configuração.
-    let settings = Settings::load();
-    // Transforma o chave em bytes.
-    let secret: &[u8] = &settings.jwt_secret_key.into_bytes();
-    // Gera a chave Hmac.
-    let key: Hmac<Sha384> = match Hmac::new_from_slice(secret) {
+/// Hash do token de renovação para armazenamento.
+/// O token já tem alta entropia, então um SHA-256 sem salt é suficiente.
+pub fn hash_refresh_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+
+    to_hex(&hasher.finalize())
+}
+
+
+/// Assina os cabeçalhos de identidade com HMAC-SHA256.
+/// Retorna `None` quando não há chave configurada.
+pub fn sign_identity(payload: &str) -> Option<String> {
+    let settings = Settings::get();
+    let secret = settings.identity_secret_key.as_ref()?;
+    let mut mac: Hmac<Sha256> = match Hmac::new_from_slice(secret.as_bytes()) {
         Ok(value) => value,
         Err(e) => {
-            error!("Can not decode JTW. Cause: {}", e);
+            error!("Can not sign identity headers. Cause: {}", e);
             return None;
         }
     };
-    let token: Token<Header, BTreeMap<String, String>, _> = match token.verify_with_key(&key) {
-        Ok(value) => value,
-        Err(e) => {
-            error!("Can not decode JTW. Cause: {}", e);
-            return None;
+
+    mac.update(payload.as_bytes());
+
+    Some(to_hex(&mac.finalize().into_bytes()))
+}
+
+
+/// Converte bytes para hexadecimal minúsculo.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::Algorithm as JwtAlgorithm;
+
+    use super::*;
+
+
+    /// Claims válidas para a configuração corrente.
+    fn claims() -> Claims {
+        let settings = Settings::get();
+        let now = now_timestamp();
+
+        Claims {
+            sub: ObjectId::new().to_hex(),
+            username: "tester".to_string(),
+            email: "tester@example.com".to_string(),
+            iss: settings.jwt_issuer.clone(),
+            aud: settings.jwt_audience.clone(),
+            exp: now + 60,
+            iat: now,
+            nbf: now,
+            jti: Uuid::new_v4().to_string(),
+            sid: ObjectId::new().to_hex(),
         }
-    };
-    let header = token.header();
-    let claims = token.claims().clone();
+    }
+
+
+    /// Assina as claims com a chave ativa, sob o `kid` e o algoritmo informados.
+    fn sign(claims: &Claims, kid: Option<&str>, algorithm: JwtAlgorithm) -> String {
+        let key = keys::active().unwrap();
+        let mut header = Header::new(algorithm);
+
+        header.kid = kid.map(str::to_string);
+
+        encode(&header, claims, &key.encoding).unwrap()
+    }
+
+
+    fn decode_with(claims: &Claims) -> Result<Claims, TokenError> {
+        let key = keys::active().unwrap();
+
+        decode_jtw(sign(claims, Some(&key.kid), key.algorithm))
+    }
+
+
+    #[test]
+    fn decodes_valid_token() {
+        let claims = claims();
+
+        assert_eq!(decode_with(&claims).unwrap().jti, claims.jti);
+    }
+
+
+    #[test]
+    fn refuses_expired_token() {
+        let claims = Claims { exp: now_timestamp() - 1, ..claims() };
+
+        assert_eq!(decode_with(&claims).unwrap_err(), TokenError::Expired);
+    }
+
+
+    #[test]
+    fn refuses_token_not_yet_valid() {
+        let claims = Claims { nbf: now_timestamp() + 30, ..claims() };
 
-    assert_eq!(header.algorithm, AlgorithmType::Hs384);
+        assert_eq!(decode_with(&claims).unwrap_err(), TokenError::NotYetValid);
+    }
+
+
+    #[test]
+    fn refuses_wrong_audience() {
+        let claims = Claims { aud: "another-audience".to_string(), ..claims() };
+
+        assert_eq!(decode_with(&claims).unwrap_err(), TokenError::Audience);
+    }
+
+
+    #[test]
+    fn refuses_wrong_issuer() {
+        let claims = Claims { iss: "another-issuer".to_string(), ..claims() };
+
+        assert_eq!(decode_with(&claims).unwrap_err(), TokenError::Issuer);
+    }
+
+
+    #[test]
+    fn refuses_unknown_key() {
+        let key = keys::active().unwrap();
+
+        assert_eq!(decode_jtw(sign(&claims(), Some("unknown"), key.algorithm)).unwrap_err(), TokenError::UnknownKey);
+        assert_eq!(decode_jtw(sign(&claims(), None, key.algorithm)).unwrap_err(), TokenError::UnknownKey);
+    }
+
+
+    #[test]
+    fn refuses_algorithm_mismatch() {
+        let key = keys::active().unwrap();
+        let token = sign(&claims(), Some(&key.kid), JwtAlgorithm::HS256);
+
+        assert_eq!(decode_jtw(token).unwrap_err(), TokenError::Algorithm);
+    }
+
+
+    #[test]
+    fn refuses_malformed_token() {
+        assert_eq!(decode_jtw("not-a-token".to_string()).unwrap_err(), TokenError::Malformed);
+    }
+
+
+    fn sha512(password: &str) -> Vec<u8> {
+        Sha512::digest(password.as_bytes()).to_vec()
+    }
+
+
+    /// Hash Argon2id com custos diferentes dos configurados.
+    fn hash_with(algorithm: Algorithm, memory_cost: u32, time_cost: u32) -> String {
+        let params = Params::new(memory_cost, time_cost, 1, None).unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+
+        Argon2::new(algorithm, Version::V0x13, params)
+            .hash_password(b"secret-password", &salt)
+            .unwrap()
+            .to_string()
+    }
+
+
+    #[test]
+    fn legacy_hex_hash() {
+        let hash = to_hex(&sha512("secret-password"));
+
+        assert!(is_valid_password("secret-password", &hash));
+        assert!(is_valid_password("secret-password", &hash.to_ascii_uppercase()));
+        assert!(!is_valid_password("wrong-password", &hash));
+        assert!(needs_rehash(&hash));
+    }
+
+
+    #[test]
+    fn legacy_raw_hash() {
+        let hash = sha512("secret-password");
 
-    Some(claims)
+        assert!(is_valid_legacy_password("secret-password", &hash));
+        assert!(!is_valid_legacy_password("wrong-password", &hash));
+        assert!(!is_valid_legacy_password("secret-password", &hash[1..]));
+    }
+
+
+    #[test]
+    fn current_hash_is_kept() {
+        let hash = hash_password("secret-password").unwrap();
+
+        assert!(is_valid_password("secret-password", &hash));
+        assert!(!is_valid_password("wrong-password", &hash));
+        assert!(!needs_rehash(&hash));
+    }
+
+
+    #[test]
+    fn changed_costs_need_rehash() {
+        let settings = Settings::get();
+
+        // A senha continua válida com os parâmetros gravados no hash.
+        let hash = hash_with(Algorithm::Argon2id, settings.argon2_memory_cost * 2, settings.argon2_time_cost);
+        assert!(is_valid_password("secret-password", &hash));
+        assert!(needs_rehash(&hash));
+
+        let hash = hash_with(Algorithm::Argon2id, settings.argon2_memory_cost, settings.argon2_time_cost + 1);
+        assert!(needs_rehash(&hash));
+
+        let hash = hash_with(Algorithm::Argon2i, settings.argon2_memory_cost, settings.argon2_time_cost);
+        assert!(needs_rehash(&hash));
+    }
 }
@@ -0,0 +1,436 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use log::{error, info, warn};
+use mongodb::bson::DateTime;
+use ring::rand::SystemRandom;
+use ring::rsa::PublicKeyComponents;
+use ring::signature::{
+    EcdsaKeyPair,
+    Ed25519KeyPair,
+    KeyPair,
+    RsaKeyPair,
+    ECDSA_P256_SHA256_FIXED_SIGNING,
+};
+use sha2::{Digest, Sha256};
+
+use crate::models::keys::{Jwk, KeyStatus, SigningKeyModel};
+use crate::settings::Settings;
+
+
+/// Chave usada para assinar e validar os tokens de acesso.
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    /// Parte pública da chave, ausente para chaves simétricas.
+    pub jwk: Option<Jwk>,
+}
+
+
+/// Chaveiro em uso: uma chave ativa e as chaves que apenas validam.
+pub struct Keyring {
+    active: Arc<SigningKey>,
+    verify: Vec<Arc<SigningKey>>,
+}
+
+
+/// Chaves carregadas do disco, a configurada sempre em primeiro lugar.
+static MATERIALS: OnceLock<Option<Vec<Arc<SigningKey>>>> = OnceLock::new();
+/// Chaveiro corrente, trocado a cada sincronização com o banco.
+static KEYRING: RwLock<Option<Arc<Keyring>>> = RwLock::new(None);
+/// Chave assimétrica dos tokens internos, fora do chaveiro de acesso.
+static INTERNAL: OnceLock<Option<Arc<SigningKey>>> = OnceLock::new();
+
+
+/// Chaves disponíveis neste processo.
+/// São lidas uma única vez, na primeira chamada.
+fn materials() -> Option<&'static [Arc<SigningKey>]> {
+    MATERIALS
+        .get_or_init(|| match load_all(Settings::get()) {
+            Ok(keys) => {
+                for key in keys.iter() {
+                    info!("Loaded {:?} signing key {}.", key.algorithm, &key.kid);
+                }
+                Some(keys)
+            },
+            Err(e) => {
+                error!("Can not load signing keys. Cause: {}", e);
+                None
+            }
+        })
+        .as_deref()
+}
+
+
+/// Chaveiro corrente.
+/// Antes da primeira sincronização, a chave configurada é a ativa.
+fn keyring() -> Option<Arc<Keyring>> {
+    if let Some(current) = KEYRING.read().ok()?.as_ref() {
+        return Some(current.clone());
+    }
+
+    let materials = materials()?;
+    let mut current = KEYRING.write().ok()?;
+    let keyring = current.get_or_insert_with(|| Arc::new(Keyring {
+        active: materials[0].clone(),
+        verify: Vec::new(),
+    }));
+
+    Some(keyring.clone())
+}
+
+
+/// Chave que assina os novos tokens.
+pub fn active() -> Option<Arc<SigningKey>> {
+    keyring().map(|keyring| keyring.active.clone())
+}
+
+
+/// Chave ativa ou apenas de validação com o `kid` informado.
+pub fn find(kid: &str) -> Option<Arc<SigningKey>> {
+    let keyring = keyring()?;
+
+    if keyring.active.kid == kid {
+        return Some(keyring.active.clone());
+    }
+
+    keyring.verify.iter().find(|key| key.kid == kid).cloned()
+}
+
+
+/// Chave que assina os tokens enviados aos micro serviços.
+/// É lida uma única vez, na primeira chamada; ausente sem `internal_jwt_private_key_path`.
+pub fn internal() -> Option<Arc<SigningKey>> {
+    INTERNAL
+        .get_or_init(|| {
+            let settings = Settings::get();
+            let path = settings.internal_jwt_private_key_path.as_ref()?;
+
+            match load_internal(path, settings.internal_jwt_key_id.clone()) {
+                Ok(key) => {
+                    info!("Loaded {:?} internal signing key {}.", key.algorithm, &key.kid);
+                    Some(Arc::new(key))
+                },
+                Err(e) => {
+                    error!("Can not load internal signing key. Cause: {}", e);
+                    None
+                }
+            }
+        })
+        .clone()
+}
+
+
+/// Partes públicas das chaves aceitas, publicadas no JWKS.
+/// Inclui a chave interna, para os micro serviços validarem seus tokens.
+pub fn published() -> Vec<Jwk> {
+    let mut jwks: Vec<Jwk> = match keyring() {
+        Some(keyring) => std::iter::once(&keyring.active)
+            .chain(keyring.verify.iter())
+            .filter_map(|key| key.jwk.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    if let Some(jwk) = internal().and_then(|key| key.jwk.clone()) {
+        jwks.push(jwk);
+    }
+
+    jwks
+}
+
+
+/// Identificadores das chaves disponíveis neste processo.
+/// O primeiro é o da chave configurada.
+pub fn available() -> Vec<String> {
+    materials()
+        .map(|keys| keys.iter().map(|key| key.kid.clone()).collect())
+        .unwrap_or_default()
+}
+
+
+/// Troca o chaveiro conforme o estado gravado no banco.
+/// Chaves sem registro só são aceitas enquanto não houver chave ativa registrada.
+pub fn apply(states: &[SigningKeyModel]) -> Result<(), String> {
+    let materials = materials().ok_or("no signing key loaded")?;
+    let find = |kid: &str| materials.iter().find(|key| key.kid == kid).cloned();
+    let now = DateTime::now();
+
+    let active = match states.iter().find(|state| state.status == KeyStatus::Active) {
+        Some(state) => find(&state.kid)
+            .ok_or_else(|| format!("active key {} is not available", state.kid))?,
+        None => materials[0].clone(),
+    };
+    let verify = states
+        .iter()
+        .filter(|state| state.status == KeyStatus::Verify)
+        .filter(|state| state.kid != active.kid)
+        .filter(|state| state.retire_at.is_none_or(|at| at > now))
+        .filter_map(|state| match find(&state.kid) {
+            Some(key) => Some(key),
+            None => {
+                warn!("Verify key {} is not available.", &state.kid);
+                None
+            }
+        })
+        .collect();
+
+    let mut current = KEYRING.write().map_err(|_| "keyring lock poisoned")?;
+    *current = Some(Arc::new(Keyring { active, verify }));
+
+    Ok(())
+}
+
+
+/// Carrega a chave configurada e as chaves de `JWT_KEYS_DIR`.
+/// O nome do arquivo, sem extensão, é o `kid` da chave.
+pub fn load_all(settings: &Settings) -> Result<Vec<Arc<SigningKey>>, String> {
+    let mut keys = vec![Arc::new(load(settings)?)];
+
+    let dir = match &settings.jwt_keys_dir {
+        Some(value) => value,
+        None => return Ok(keys),
+    };
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("can not read {}: {}", dir, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+
+    paths.sort();
+
+    for path in paths {
+        let kid = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(value) => value.to_string(),
+            None => continue,
+        };
+        let extension = path.extension().and_then(|ext| ext.to_str());
+
+        if !matches!(extension, Some("pem") | Some("key")) {
+            continue;
+        }
+
+        let content = fs::read(&path)
+            .map_err(|e| format!("can not read {}: {}", path.display(), e))?;
+        let key = match extension {
+            Some("pem") => load_pem(infer_algorithm(&content)?, &content, Some(kid))?,
+            _ => {
+                let secret = String::from_utf8_lossy(&content);
+                let secret = secret.trim().as_bytes();
+
+                // Os segredos dos arquivos seguem a mesma regra do `jwt_secret_key`.
+                settings.check_secret(&path.display().to_string(), secret)?;
+                secret_key(secret, kid)
+            },
+        };
+
+        if keys.iter().any(|loaded| loaded.kid == key.kid) {
+            return Err(format!("duplicated key id {}", key.kid));
+        }
+
+        keys.push(Arc::new(key));
+    }
+
+    Ok(keys)
+}
+
+
+/// Monta a chave de assinatura conforme o algoritmo configurado.
+pub fn load(settings: &Settings) -> Result<SigningKey, String> {
+    let algorithm = parse_algorithm(&settings.jwt_algorithm)?;
+
+    if algorithm == Algorithm::HS384 {
+        let kid = settings.jwt_key_id.clone().unwrap_or_else(|| "default".to_string());
+
+        return Ok(secret_key(settings.jwt_secret_key.as_bytes(), kid));
+    }
+
+    let path = match &settings.jwt_private_key_path {
+        Some(value) => value,
+        None => return Err("`jwt_private_key_path` is required for asymmetric algorithms".to_string()),
+    };
+    let content = fs::read(path)
+        .map_err(|e| format!("can not read {}: {}", path, e))?;
+
+    load_pem(algorithm, &content, settings.jwt_key_id.clone())
+}
+
+
+/// Monta a chave interna a partir da chave privada PEM.
+/// O algoritmo é descoberto pelo tipo da chave, sempre assimétrico.
+fn load_internal(path: &str, kid: Option<String>) -> Result<SigningKey, String> {
+    let content = fs::read(path)
+        .map_err(|e| format!("can not read {}: {}", path, e))?;
+
+    load_pem(infer_algorithm(&content)?, &content, kid)
+}
+
+
+/// Monta a chave simétrica HS384.
+fn secret_key(secret: &[u8], kid: String) -> SigningKey {
+    SigningKey {
+        kid,
+        algorithm: Algorithm::HS384,
+        encoding: EncodingKey::from_secret(secret),
+        decoding: DecodingKey::from_secret(secret),
+        jwk: None,
+    }
+}
+
+
+/// Descobre o algoritmo pelo tipo da chave privada PEM.
+fn infer_algorithm(content: &[u8]) -> Result<Algorithm, String> {
+    let parsed = pem::parse(content)
+        .map_err(|e| format!("invalid PEM: {}", e))?;
+    let der = parsed.contents();
+
+    if parsed.tag() == "RSA PRIVATE KEY" || RsaKeyPair::from_pkcs8(der).is_ok() {
+        return Ok(Algorithm::RS256);
+    }
+    if EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, der, &SystemRandom::new()).is_ok() {
+        return Ok(Algorithm::ES256);
+    }
+    if Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).is_ok() {
+        return Ok(Algorithm::EdDSA);
+    }
+
+    Err("unsupported private key".to_string())
+}
+
+
+/// Monta a chave assimétrica a partir de uma chave privada PEM.
+/// RSA aceita PKCS#1 ou PKCS#8, EC e Ed25519 apenas PKCS#8.
+pub fn load_pem(algorithm: Algorithm, content: &[u8], kid: Option<String>) -> Result<SigningKey, String> {
+    let parsed = pem::parse(content)
+        .map_err(|e| format!("invalid PEM: {}", e))?;
+    let der = parsed.contents();
+
+    let (encoding, mut jwk) = match algorithm {
+        Algorithm::RS256 => {
+            let pair = match parsed.tag() {
+                "RSA PRIVATE KEY" => RsaKeyPair::from_der(der),
+                _ => RsaKeyPair::from_pkcs8(der),
+            }.map_err(|e| format!("invalid RSA key: {}", e))?;
+            let public: PublicKeyComponents<Vec<u8>> = pair.public().into();
+            let encoding = EncodingKey::from_rsa_pem(content)
+                .map_err(|e| format!("invalid RSA key: {}", e))?;
+
+            (encoding, Jwk {
+                kty: "RSA".to_string(),
+                key_use: "sig".to_string(),
+                alg: "RS256".to_string(),
+                kid: String::new(),
+                n: Some(URL_SAFE_NO_PAD.encode(&public.n)),
+                e: Some(URL_SAFE_NO_PAD.encode(&public.e)),
+                crv: None,
+                x: None,
+                y: None,
+            })
+        },
+        Algorithm::ES256 => {
+            let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, der, &SystemRandom::new())
+                .map_err(|e| format!("invalid EC key: {}", e))?;
+            // Ponto não comprimido: 0x04 || x || y.
+            let point = pair.public_key().as_ref();
+
+            let encoding = EncodingKey::from_ec_pem(content)
+                .map_err(|e| format!("invalid EC key: {}", e))?;
+
+            (encoding, Jwk {
+                kty: "EC".to_string(),
+                key_use: "sig".to_string(),
+                alg: "ES256".to_string(),
+                kid: String::new(),
+                n: None,
+                e: None,
+                crv: Some("P-256".to_string()),
+                x: Some(URL_SAFE_NO_PAD.encode(&point[1..33])),
+                y: Some(URL_SAFE_NO_PAD.encode(&point[33..65])),
+            })
+        },
+        Algorithm::EdDSA => {
+            let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+                .map_err(|e| format!("invalid Ed25519 key: {}", e))?;
+
+            let encoding = EncodingKey::from_ed_pem(content)
+                .map_err(|e| format!("invalid Ed25519 key: {}", e))?;
+
+            (encoding, Jwk {
+                kty: "OKP".to_string(),
+                key_use: "sig".to_string(),
+                alg: "EdDSA".to_string(),
+                kid: String::new(),
+                n: None,
+                e: None,
+                crv: Some("Ed25519".to_string()),
+                x: Some(URL_SAFE_NO_PAD.encode(pair.public_key().as_ref())),
+                y: None,
+            })
+        },
+        _ => return Err(format!("unsupported algorithm {:?}", algorithm)),
+    };
+
+    jwk.kid = kid.unwrap_or_else(|| thumbprint(&jwk));
+
+    let decoding = decoding_key(&jwk)?;
+
+    Ok(SigningKey {
+        kid: jwk.kid.clone(),
+        algorithm,
+        encoding,
+        decoding,
+        jwk: Some(jwk),
+    })
+}
+
+
+/// Converte o nome configurado no algoritmo de assinatura.
+pub fn parse_algorithm(name: &str) -> Result<Algorithm, String> {
+    match name.to_ascii_uppercase().as_str() {
+        "HS384" => Ok(Algorithm::HS384),
+        "RS256" => Ok(Algorithm::RS256),
+        "ES256" => Ok(Algorithm::ES256),
+        "EDDSA" => Ok(Algorithm::EdDSA),
+        _ => Err(format!("unsupported JWT algorithm {}", name)),
+    }
+}
+
+
+/// Monta a chave de validação a partir da parte pública.
+fn decoding_key(jwk: &Jwk) -> Result<DecodingKey, String> {
+    let missing = || "incomplete public key".to_string();
+    let result = match jwk.kty.as_str() {
+        "RSA" => DecodingKey::from_rsa_components(
+            jwk.n.as_ref().ok_or_else(missing)?,
+            jwk.e.as_ref().ok_or_else(missing)?,
+        ),
+        "EC" => DecodingKey::from_ec_components(
+            jwk.x.as_ref().ok_or_else(missing)?,
+            jwk.y.as_ref().ok_or_else(missing)?,
+        ),
+        _ => DecodingKey::from_ed_components(jwk.x.as_ref().ok_or_else(missing)?),
+    };
+
+    result.map_err(|e| format!("invalid public key: {}", e))
+}
+
+
+/// Identificador da chave pelo thumbprint SHA-256 do JWK (RFC 7638).
+fn thumbprint(jwk: &Jwk) -> String {
+    let value = |field: &Option<String>| field.clone().unwrap_or_default();
+    let canonical = match jwk.kty.as_str() {
+        "RSA" => format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, value(&jwk.e), value(&jwk.n)),
+        "EC" => format!(
+            r#"{{"crv":"{}","kty":"EC","x":"{}","y":"{}"}}"#,
+            value(&jwk.crv), value(&jwk.x), value(&jwk.y),
+        ),
+        _ => format!(r#"{{"crv":"{}","kty":"OKP","x":"{}"}}"#, value(&jwk.crv), value(&jwk.x)),
+    };
+
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
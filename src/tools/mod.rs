@@ -1 +1,3 @@
-pub mod hasher;
\ No newline at end of file
+pub mod hasher;
+pub mod keys;
+pub mod validators;
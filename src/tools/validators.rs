@@ -0,0 +1,59 @@
+use url::Url;
+
+
+/// Prefixo reservado para as rotas da própria API.
+const RESERVED_PREFIX: &str = "/api/v1";
+
+
+/// Valida e normaliza o host de um micro serviço.
+/// Aceita apenas http ou https, com porta e caminho base opcionais.
+pub fn validate_host(host: &str) -> Result<String, String> {
+    let url = match Url::parse(host.trim()) {
+        Ok(value) => value,
+        Err(e) => return Err(format!("Invalid host URL: {}.", e)),
+    };
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err("Host scheme must be http or https.".to_string());
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("Host URL must have a host name.".to_string());
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("Host URL can not carry credentials.".to_string());
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err("Host URL can not have query or fragment.".to_string());
+    }
+
+    // Remove a barra final para concatenar os caminhos depois.
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+
+/// Valida e normaliza os prefixos de rota de um micro serviço.
+pub fn validate_routes(routes: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+
+    for route in routes.iter() {
+        let route = route.trim().trim_end_matches('/');
+
+        if !route.starts_with('/') {
+            return Err(format!("Route {} must start with '/'.", route));
+        }
+        if route.chars().any(|c| c.is_whitespace() || c == '?' || c == '#') {
+            return Err(format!("Route {} has invalid characters.", route));
+        }
+        if route.split('/').any(|segment| segment == "." || segment == "..") {
+            return Err(format!("Route {} can not have relative segments.", route));
+        }
+        if route == RESERVED_PREFIX || route.starts_with(&format!("{}/", RESERVED_PREFIX)) {
+            return Err(format!("Route {} is reserved.", route));
+        }
+        if !normalized.iter().any(|item| item == route) {
+            normalized.push(route.to_string());
+        }
+    }
+
+    Ok(normalized)
+}
@@ -0,0 +1,158 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use bson::oid::ObjectId;
+use log::warn;
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::middlewares::auth::Authenticated;
+use crate::middlewares::permissions::RequirePermission;
+use crate::models::groups::{GroupModel, GroupSerialize};
+use crate::services::groups::GroupService;
+use crate::services::permissions::PermissionService;
+use crate::views::parse_lookup;
+use crate::views::payloads::{GroupPayload, GroupPermissionPayload, GroupUserPayload};
+
+
+/// Rota para listar os grupos.
+#[get("/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn list(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+) -> Result<HttpResponse, AppError> {
+    let groups = service.list().await?;
+
+    Ok(HttpResponse::Ok().json(groups))
+}
+
+
+/// Rota para capturar um único grupo.
+#[get("/{group_id}/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn get(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let group_id = parse_lookup(&path.into_inner().0)?;
+
+    match service.get_by_id(&group_id).await? {
+        Some(group) => Ok(HttpResponse::Ok().json(group)),
+        None => {
+            warn!("Not found group by ID {} on data base.", &group_id);
+            Err(AppError::NotFound("group"))
+        }
+    }
+}
+
+
+/// Rota para cadastrar um grupo.
+/// Todas as permissões informadas precisam existir.
+#[post("/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn create(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    permissions: web::Data<PermissionService>,
+    payloads: web::Json<GroupPayload>,
+) -> Result<HttpResponse, AppError> {
+    let payloads = payloads.into_inner();
+
+    if payloads.name.trim().is_empty() {
+        return Err(AppError::Validation("Group name can not be empty.".to_string()));
+    }
+
+    let mut models = Vec::new();
+
+    for lookup in payloads.permissions.iter() {
+        let permission_id = parse_lookup(lookup)?;
+
+        match permissions.get_model_by_id(&permission_id).await? {
+            Some(permission) => models.push(permission),
+            None => return Err(AppError::NotFound("permission")),
+        };
+    }
+
+    let group = GroupModel {
+        _id: ObjectId::new(),
+        name: payloads.name,
+        permissions: models,
+        actions: payloads.actions,
+        created_at: DateTime::now(),
+    };
+
+    service.create(&group).await?;
+
+    Ok(HttpResponse::Created().json(GroupSerialize::from(&group)))
+}
+
+
+/// Rota para adicionar uma permissão ao grupo.
+#[post("/{group_id}/permissions/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn add_permission(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    permissions: web::Data<PermissionService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<GroupPermissionPayload>,
+) -> Result<HttpResponse, AppError> {
+    let group_id = parse_lookup(&path.into_inner().0)?;
+    let permission_id = parse_lookup(&payloads.permission)?;
+    let permission = match permissions.get_model_by_id(&permission_id).await? {
+        Some(value) => value,
+        None => return Err(AppError::NotFound("permission")),
+    };
+
+    service.add_permission(&group_id, &permission).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para remover uma permissão do grupo.
+#[delete("/{group_id}/permissions/{permission_id}/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn remove_permission(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let (group_id, permission_id) = path.into_inner();
+    let group_id = parse_lookup(&group_id)?;
+    let permission_id = parse_lookup(&permission_id)?;
+
+    service.remove_permission(&group_id, &permission_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para relacionar um usuário ao grupo.
+#[post("/{group_id}/users/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn add_user(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<GroupUserPayload>,
+) -> Result<HttpResponse, AppError> {
+    let group_id = parse_lookup(&path.into_inner().0)?;
+    let user_id = parse_lookup(&payloads.user)?;
+
+    service.add_user(&group_id, &user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para remover um usuário do grupo.
+#[delete("/{group_id}/users/{user_id}/", wrap = "RequirePermission::new(\"groups\")")]
+pub async fn remove_user(
+    _auth: Authenticated,
+    service: web::Data<GroupService>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let (group_id, user_id) = path.into_inner();
+    let group_id = parse_lookup(&group_id)?;
+    let user_id = parse_lookup(&user_id)?;
+
+    match service.remove_user(&group_id, &user_id).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Err(AppError::NotFound("relationship")),
+    }
+}
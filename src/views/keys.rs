@@ -0,0 +1,13 @@
+use actix_web::{get, HttpResponse};
+
+use crate::models::keys::JwkSet;
+use crate::tools::keys;
+
+
+/// Rota pública com as chaves de validação dos tokens.
+/// Inclui a chave ativa e as que ainda validam tokens emitidos.
+/// Chaves simétricas nunca são publicadas.
+#[get("/.well-known/jwks.json")]
+pub async fn jwks() -> HttpResponse {
+    HttpResponse::Ok().json(JwkSet { keys: keys::published() })
+}
@@ -0,0 +1,118 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use bson::oid::ObjectId;
+use log::warn;
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::middlewares::auth::Authenticated;
+use crate::middlewares::permissions::RequirePermission;
+use crate::models::micro_services::{MicroServiceModel, MicroServiceSerialize};
+use crate::services::micro_services::RegistryService;
+use crate::tools::validators;
+use crate::views::parse_lookup;
+use crate::views::payloads::MicroServicePayload;
+
+
+/// Valida o cadastro e devolve nome, host e rotas normalizados.
+fn validate(payloads: MicroServicePayload) -> Result<(String, String, Vec<String>), AppError> {
+    if payloads.name.trim().is_empty() {
+        return Err(AppError::Validation("Micro service name can not be empty.".to_string()));
+    }
+
+    let host = validators::validate_host(&payloads.host).map_err(AppError::Validation)?;
+    let routes = validators::validate_routes(&payloads.routes).map_err(AppError::Validation)?;
+
+    Ok((payloads.name, host, routes))
+}
+
+
+/// Rota para listar os micro serviços.
+#[get("/", wrap = "RequirePermission::new(\"micro_services\")")]
+pub async fn list(
+    _auth: Authenticated,
+    service: web::Data<RegistryService>,
+) -> Result<HttpResponse, AppError> {
+    let data: Vec<MicroServiceSerialize> = service
+        .list()
+        .await?
+        .iter()
+        .map(MicroServiceSerialize::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(data))
+}
+
+
+/// Rota para capturar um único micro serviço.
+#[get("/{micro_service_id}/", wrap = "RequirePermission::new(\"micro_services\")")]
+pub async fn get(
+    _auth: Authenticated,
+    service: web::Data<RegistryService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let micro_service_id = parse_lookup(&path.into_inner().0)?;
+
+    match service.get_by_id(&micro_service_id).await? {
+        Some(micro_service) => Ok(HttpResponse::Ok().json(micro_service)),
+        None => {
+            warn!("Not found micro service by ID {} on data base.", &micro_service_id);
+            Err(AppError::NotFound("micro service"))
+        }
+    }
+}
+
+
+/// Rota para registrar um micro serviço.
+#[post("/", wrap = "RequirePermission::new(\"micro_services\")")]
+pub async fn register(
+    _auth: Authenticated,
+    service: web::Data<RegistryService>,
+    payloads: web::Json<MicroServicePayload>,
+) -> Result<HttpResponse, AppError> {
+    let (name, host, routes) = validate(payloads.into_inner())?;
+    let micro_service = MicroServiceModel {
+        _id: ObjectId::new(),
+        name,
+        host,
+        routes,
+        created_at: DateTime::now(),
+    };
+
+    service.create(&micro_service).await?;
+
+    Ok(HttpResponse::Created().json(MicroServiceSerialize::from(&micro_service)))
+}
+
+
+/// Rota para atualizar o cadastro de um micro serviço.
+#[put("/{micro_service_id}/", wrap = "RequirePermission::new(\"micro_services\")")]
+pub async fn update(
+    _auth: Authenticated,
+    service: web::Data<RegistryService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<MicroServicePayload>,
+) -> Result<HttpResponse, AppError> {
+    let micro_service_id = parse_lookup(&path.into_inner().0)?;
+    let (name, host, routes) = validate(payloads.into_inner())?;
+
+    match service.update(&micro_service_id, &name, &host, &routes).await? {
+        Some(micro_service) => Ok(HttpResponse::Ok().json(micro_service)),
+        None => Err(AppError::NotFound("micro service")),
+    }
+}
+
+
+/// Rota para remover o registro de um micro serviço.
+#[delete("/{micro_service_id}/", wrap = "RequirePermission::new(\"micro_services\")")]
+pub async fn deregister(
+    _auth: Authenticated,
+    service: web::Data<RegistryService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let micro_service_id = parse_lookup(&path.into_inner().0)?;
+
+    match service.delete(&micro_service_id).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Err(AppError::NotFound("micro service")),
+    }
+}
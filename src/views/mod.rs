@@ -1,2 +1,96 @@
-mod payloads;
-pub mod users;
\ No newline at end of file
+pub(crate) mod payloads;
+pub mod groups;
+pub mod keys;
+pub mod micro_services;
+pub mod permissions;
+pub mod proxy;
+pub mod users;
+
+use actix_web::{web, HttpRequest};
+use bson::oid::ObjectId;
+use log::{debug, error};
+
+use crate::errors::AppError;
+
+
+/// Registra todas as rotas da API em seus escopos versionados.
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    // Corpo, query e caminho inválidos também respondem no formato de problema.
+    cfg.app_data(web::JsonConfig::default().error_handler(|e, _| invalid_request(e.to_string())));
+    cfg.app_data(web::QueryConfig::default().error_handler(|e, _| invalid_request(e.to_string())));
+    cfg.app_data(web::PathConfig::default().error_handler(|e, _| invalid_request(e.to_string())));
+    cfg.service(keys::jwks);
+    cfg.service(
+        web::scope("/api/v1")
+            .service(
+                web::scope("/users")
+                    .service(users::login)
+                    .service(users::refresh)
+                    .service(users::logout)
+                    .service(users::own_sessions)
+                    .service(users::terminate_own_session)
+                    .service(users::list)
+                    .service(users::create)
+                    .service(users::get)
+                    .service(users::user_sessions)
+                    .service(users::terminate_session_of)
+                    .service(users::update)
+                    .service(users::partial_update)
+                    .service(users::deactivate)
+                    .service(users::revoke)
+                    .service(users::unlock)
+                    .service(users::remove)
+            )
+            .service(
+                web::scope("/permissions")
+                    .service(permissions::list)
+                    .service(permissions::create)
+                    .service(permissions::link_micro_service)
+                    .service(permissions::unlink_micro_service)
+            )
+            .service(
+                web::scope("/groups")
+                    .service(groups::list)
+                    .service(groups::create)
+                    .service(groups::get)
+                    .service(groups::add_permission)
+                    .service(groups::remove_permission)
+                    .service(groups::add_user)
+                    .service(groups::remove_user)
+            )
+            .service(
+                web::scope("/micro_services")
+                    .service(micro_services::list)
+                    .service(micro_services::register)
+                    .service(micro_services::get)
+                    .service(micro_services::update)
+                    .service(micro_services::deregister)
+            )
+    );
+}
+
+
+
+/// Converte o identificador da rota em ObjectId.
+pub(crate) fn parse_lookup(lookup: &str) -> Result<ObjectId, AppError> {
+    match ObjectId::parse_str(lookup) {
+        Ok(id) => Ok(id),
+        Err(e) => {
+            error!("Can not parse ID {}, cause: {}", lookup, e);
+            Err(AppError::Validation("Invalid lookup content.".to_string()))
+        }
+    }
+}
+
+
+/// Endereço IP de quem abriu a conexão.
+pub(crate) fn client_ip(req: &HttpRequest) -> Option<String> {
+    req.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+
+/// Erro dos extratores do actix como falha de validação.
+fn invalid_request(reason: String) -> actix_web::Error {
+    debug!("Refused request, cause: {}", reason);
+    AppError::Validation(reason).into()
+}
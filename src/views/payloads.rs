@@ -1,7 +1,152 @@
 use serde::Deserialize;
 
+use crate::models::groups::Actions;
+
 #[derive(Debug, Deserialize)]
 pub struct LoginPayload{
     pub username: String,
     pub password: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshPayload{
+    pub refresh_token: String,
+}
+
+/// Valida os campos básicos de um usuário.
+fn validate_user_fields(username: Option<&String>, email: Option<&String>, password: Option<&String>) -> Result<(), String> {
+    if let Some(value) = username {
+        if value.trim().is_empty() {
+            return Err("Username can not be empty.".to_string());
+        }
+    }
+    if let Some(value) = email {
+        if !value.contains('@') {
+            return Err("Invalid email.".to_string());
+        }
+    }
+    if let Some(value) = password {
+        if value.len() < 8 {
+            return Err("Password must have at least 8 characters.".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserPayload{
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_superuser: bool,
+} impl CreateUserPayload {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_user_fields(Some(&self.username), Some(&self.email), Some(&self.password))
+    }
+}
+
+
+/// Atualização completa, a senha só é trocada se informada.
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserPayload{
+    pub username: String,
+    pub email: String,
+    pub password: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub is_active: bool,
+    pub is_superuser: bool,
+} impl UpdateUserPayload {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_user_fields(Some(&self.username), Some(&self.email), self.password.as_ref())
+    }
+}
+
+
+/// Atualização parcial, apenas os campos informados são alterados.
+#[derive(Debug, Deserialize)]
+pub struct PatchUserPayload{
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub is_active: Option<bool>,
+    pub is_superuser: Option<bool>,
+} impl PatchUserPayload {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_user_fields(self.username.as_ref(), self.email.as_ref(), self.password.as_ref())
+    }
+}
+
+
+/// Filtros e paginação da listagem de usuários.
+#[derive(Debug, Deserialize)]
+pub struct ListUsersQuery{
+    pub is_active: Option<bool>,
+    #[serde(default)]
+    pub skip: u64,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+
+fn default_true() -> bool {
+    true
+}
+
+
+fn default_limit() -> i64 {
+    50
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct PermissionPayload{
+    pub name: String,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct GroupPayload{
+    pub name: String,
+    pub actions: Actions,
+    /// IDs das permissões iniciais do grupo.
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct GroupPermissionPayload{
+    pub permission: String,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct GroupUserPayload{
+    pub user: String,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct MicroServiceLinkPayload{
+    pub micro_service: String,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct MicroServicePayload{
+    pub name: String,
+    pub host: String,
+    /// Prefixos de rota atendidos pelo micro serviço.
+    #[serde(default)]
+    pub routes: Vec<String>,
+}
@@ -0,0 +1,83 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+
+use crate::errors::AppError;
+use crate::middlewares::auth::Authenticated;
+use crate::middlewares::permissions::RequirePermission;
+use crate::models::permissions::{PermissionModel, PermissionSerialize};
+use crate::services::permissions::PermissionService;
+use crate::views::parse_lookup;
+use crate::views::payloads::{MicroServiceLinkPayload, PermissionPayload};
+
+
+/// Rota para listar as permissões.
+#[get("/", wrap = "RequirePermission::new(\"permissions\")")]
+pub async fn list(
+    _auth: Authenticated,
+    service: web::Data<PermissionService>,
+) -> Result<HttpResponse, AppError> {
+    let permissions = service.list().await?;
+
+    Ok(HttpResponse::Ok().json(permissions))
+}
+
+
+/// Rota para cadastrar uma permissão.
+#[post("/", wrap = "RequirePermission::new(\"permissions\")")]
+pub async fn create(
+    _auth: Authenticated,
+    service: web::Data<PermissionService>,
+    payloads: web::Json<PermissionPayload>,
+) -> Result<HttpResponse, AppError> {
+    let name = payloads.into_inner().name;
+
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Permission name can not be empty.".to_string()));
+    }
+
+    let permission = PermissionModel {
+        _id: ObjectId::new(),
+        name,
+        created_at: DateTime::now(),
+    };
+
+    service.create(&permission).await?;
+
+    Ok(HttpResponse::Created().json(PermissionSerialize::from(&permission)))
+}
+
+
+/// Rota para relacionar a permissão a um micro serviço.
+#[post("/{permission_id}/micro_services/", wrap = "RequirePermission::new(\"permissions\")")]
+pub async fn link_micro_service(
+    _auth: Authenticated,
+    service: web::Data<PermissionService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<MicroServiceLinkPayload>,
+) -> Result<HttpResponse, AppError> {
+    let permission = parse_lookup(&path.into_inner().0)?;
+    let micro_service = parse_lookup(&payloads.micro_service)?;
+
+    service.link_micro_service(&permission, &micro_service).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para desfazer o relacionamento com um micro serviço.
+#[delete("/{permission_id}/micro_services/{micro_service_id}/", wrap = "RequirePermission::new(\"permissions\")")]
+pub async fn unlink_micro_service(
+    _auth: Authenticated,
+    service: web::Data<PermissionService>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let (permission, micro_service) = path.into_inner();
+    let permission = parse_lookup(&permission)?;
+    let micro_service = parse_lookup(&micro_service)?;
+
+    match service.unlink_micro_service(&permission, &micro_service).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Err(AppError::NotFound("relationship")),
+    }
+}
@@ -0,0 +1,242 @@
+use std::io;
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, warn};
+use reqwest::header::HeaderMap;
+use reqwest::{Body, Method};
+
+use crate::errors::AppError;
+use crate::middlewares::auth::{Authenticated, Principal};
+use crate::middlewares::request_id::{RequestId, REQUEST_ID_HEADER};
+use crate::models::groups::GroupModel;
+use crate::services::authorization::{self, Action, AuthorizationService};
+use crate::services::proxy::{is_forwardable, is_safe_path, to_header, ProxyError, ProxyService, Upstream};
+use crate::settings::{Profile, Settings};
+use crate::tools::hasher;
+use crate::views::client_ip;
+
+
+/// Prefixo dos cabeçalhos de identidade confiáveis.
+const IDENTITY_PREFIX: &str = "x-user-";
+/// Credenciais do cliente com o gateway, que nunca chegam aos micro serviços.
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie"];
+
+
+/// Encaminha as requisições não atendidas pela API aos micro serviços.
+/// Exige autenticação e as permissões relacionadas ao micro serviço.
+pub async fn forward(
+    req: HttpRequest,
+    payload: web::Payload,
+    auth: Authenticated,
+    proxy: web::Data<ProxyService>,
+    authorization: web::Data<AuthorizationService>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let path = req.path();
+
+    // O caminho é repassado como está, então não pode ser reinterpretado adiante.
+    if !is_safe_path(path) {
+        warn!("Refused unsafe path {} from {}.", path, &principal.user.username);
+        return Err(AppError::Validation("Path must not contain dot or empty segments.".to_string()));
+    }
+
+    let micro_service = match proxy.resolve(path).await? {
+        Some(value) => value,
+        None => {
+            debug!("No micro service owns path {}.", path);
+            return Err(AppError::NotFound("route"));
+        },
+    };
+
+    let permissions = proxy.permissions_of(&micro_service._id).await?;
+    let action = Action::from_method(req.method());
+    let groups = authorization.groups_of(&principal.user._id).await?;
+
+    if let Err(denied) = authorization::evaluate(&principal.user, &groups, &permissions, action) {
+        warn!("User {} denied on micro service {}.", &principal.user.username, &micro_service.name);
+        return Err(denied.into());
+    }
+
+    let method = match Method::from_bytes(req.method().as_str().as_bytes()) {
+        Ok(value) => value,
+        Err(_) => return Err(AppError::Validation("Unsupported HTTP method.".to_string())),
+    };
+    let url = match req.uri().query() {
+        Some(query) => format!("{}{}?{}", &micro_service.host, path, query),
+        None => format!("{}{}", &micro_service.host, path),
+    };
+    let mut headers = upstream_headers(&req);
+
+    identity_headers(&mut headers, &principal, &groups, req.method().as_str(), path);
+
+    // Troca o token do navegador por um token interno do micro serviço.
+    // Sem a chave interna, só fora de produção a chamada segue sem ele.
+    let internal = hasher::generate_internal_jwt(
+        &principal.user,
+        &micro_service.name,
+        effective_permissions(&principal, &groups),
+    );
+
+    match internal {
+        Some(token) => {
+            if let Some((name, value)) = to_header("authorization", format!("Bearer {}", token).as_bytes()) {
+                headers.insert(name, value);
+            }
+        },
+        None if Settings::get().profile != Profile::Production => {
+            warn!("Calling {} without an internal token.", &micro_service.name);
+        },
+        None => {
+            error!("Can not call {} without an internal signing key.", &micro_service.name);
+            return Err(AppError::internal_token_unavailable());
+        },
+    }
+
+    let upstream = Upstream {
+        method,
+        url,
+        headers,
+        body: upstream_body(&req, payload),
+    };
+
+    let response = proxy.forward(upstream).await?;
+    let status = match StatusCode::from_u16(response.status().as_u16()) {
+        Ok(value) => value,
+        Err(_) => return Err(ProxyError::Unavailable.into()),
+    };
+    let mut builder = HttpResponse::build(status);
+
+    for (name, value) in response.headers().iter() {
+        if is_forwardable(name.as_str()) {
+            builder.append_header((name.as_str(), value.as_bytes()));
+        }
+    }
+
+    Ok(builder.streaming(response.bytes_stream()))
+}
+
+
+/// Copia os cabeçalhos do cliente e acrescenta os de encaminhamento.
+/// Cabeçalhos de identidade e credenciais do cliente são descartados:
+/// apenas o token interno segue como `Authorization`.
+fn upstream_headers(req: &HttpRequest) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    for (name, value) in req.headers().iter() {
+        let name = name.as_str();
+
+        if !is_forwardable(name) || name.starts_with(IDENTITY_PREFIX) || CREDENTIAL_HEADERS.contains(&name) {
+            continue;
+        }
+        if let Some((name, value)) = to_header(name, value.as_bytes()) {
+            headers.append(name, value);
+        }
+    }
+
+    // Mantém o mesmo identificador da requisição nos logs do micro serviço.
+    if let Some(RequestId(id)) = req.extensions().get::<RequestId>() {
+        if let Some((name, value)) = to_header(REQUEST_ID_HEADER.as_str(), id.as_bytes()) {
+            headers.insert(name, value);
+        }
+    }
+
+    let info = req.connection_info();
+    // O IP vem da conexão: `Forwarded` e `X-Forwarded-For` são do próprio cliente.
+    let forwarded = [
+        ("x-forwarded-for", client_ip(req).unwrap_or_default()),
+        ("x-forwarded-proto", info.scheme().to_string()),
+        ("x-forwarded-host", info.host().to_string()),
+    ];
+
+    for (name, value) in forwarded.iter() {
+        if let Some((name, value)) = to_header(name, value.as_bytes()) {
+            headers.insert(name, value);
+        }
+    }
+
+    headers
+}
+
+
+/// Acrescenta os cabeçalhos com a identidade verificada do usuário.
+/// Com `IDENTITY_SECRET_KEY` configurada, assina o conteúdo com HMAC-SHA256
+/// sobre as linhas: id, username, email, grupos, permissões, timestamp,
+/// método e caminho, nessa ordem.
+fn identity_headers(headers: &mut HeaderMap, principal: &Principal, groups: &[GroupModel], method: &str, path: &str) {
+    let user = &principal.user;
+    let group_names: Vec<&str> = groups
+        .iter()
+        .map(|group| group.name.as_str())
+        .collect();
+    let permissions = effective_permissions(principal, groups).join(",");
+    let identity = [
+        ("x-user-id", user._id.to_hex()),
+        ("x-user-username", user.username.clone()),
+        ("x-user-email", user.email.clone()),
+        ("x-user-groups", group_names.join(",")),
+        ("x-user-permissions", permissions),
+        ("x-user-timestamp", hasher::now_timestamp().to_string()),
+    ];
+
+    for (name, value) in identity.iter() {
+        if let Some((name, value)) = to_header(name, value.as_bytes()) {
+            headers.insert(name, value);
+        }
+    }
+
+    let mut canonical: Vec<&str> = identity
+        .iter()
+        .map(|(_, value)| value.as_str())
+        .collect();
+
+    canonical.push(method);
+    canonical.push(path);
+
+    if let Some(signature) = hasher::sign_identity(&canonical.join("\n")) {
+        if let Some((name, value)) = to_header("x-user-signature", signature.as_bytes()) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+
+/// Permissões efetivas do usuário, `*` para superusuários.
+fn effective_permissions(principal: &Principal, groups: &[GroupModel]) -> Vec<String> {
+    match principal.user.is_superuser {
+        true => vec!["*".to_string()],
+        false => authorization::effective_permissions(groups),
+    }
+}
+
+
+/// Repassa o corpo da requisição em fluxo, sem carregá-lo em memória.
+/// Requisições sem corpo declarado seguem sem corpo.
+fn upstream_body(req: &HttpRequest, mut payload: web::Payload) -> Option<Body> {
+    let has_body = req.headers().contains_key("content-length")
+        || req.headers().contains_key("transfer-encoding");
+
+    if !has_body {
+        return None;
+    }
+
+    // O payload do actix não é `Send`, então é lido por uma tarefa local
+    // e entregue ao cliente HTTP por um canal.
+    let (mut sender, receiver) = mpsc::channel::<Result<web::Bytes, io::Error>>(8);
+
+    actix_web::rt::spawn(async move {
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|e| io::Error::other(e.to_string()));
+            let failed = chunk.is_err();
+
+            if sender.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    Some(Body::wrap_stream(receiver))
+}
+
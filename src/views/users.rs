@@ -1,81 +1,616 @@
-use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse};
-use log::{error, warn, debug};
+use actix_web::http::header::USER_AGENT;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+use log::{error, warn, debug, info};
 use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
 
-use crate::models::users::Login;
-use crate::services::{
-    MongoService,
-    users::UserService,
+use crate::errors::AppError;
+use crate::middlewares::auth::{Authenticated, Principal};
+use crate::middlewares::permissions::RequirePermission;
+use crate::models::sessions::{SessionModel, SessionSerialize};
+use crate::models::users::{Login, UserChanges, UserModel, UserSerialize};
+use crate::services::sessions::SessionService;
+use crate::services::throttle::{self, ThrottleService};
+use crate::services::tokens::{RefreshError, TokenService};
+use crate::services::users::UserService;
+use crate::settings::Settings;
+use crate::views::{client_ip, parse_lookup};
+use crate::views::payloads::{
+    CreateUserPayload,
+    ListUsersQuery,
+    LoginPayload,
+    PatchUserPayload,
+    RefreshPayload,
+    UpdateUserPayload,
 };
-use crate::views::payloads::LoginPayload;
 use crate::tools::hasher;
 
 
 /// Rota para excução do login dos usuários.
 #[post("/login/")]
-pub async fn login(payloads: web::Json<LoginPayload>) -> HttpResponse {
-    // Inicia o serviço de consultas de usuário.
-    let service = UserService::new(MongoService::new().await);
+pub async fn login(
+    req: HttpRequest,
+    service: web::Data<UserService>,
+    tokens: web::Data<TokenService>,
+    sessions: web::Data<SessionService>,
+    throttle: web::Data<ThrottleService>,
+    payloads: web::Json<LoginPayload>,
+) -> Result<HttpResponse, AppError> {
+    let settings = Settings::get();
+    let ip = client_ip(&req);
+
+    // Recusa tentativas durante a espera ou o bloqueio.
+    let mut keys = vec![throttle::user_key(&payloads.username)];
+    if let Some(value) = &ip {
+        keys.push(throttle::ip_key(value));
+    }
+    if let Err(retry_after) = throttle.check(&keys).await {
+        warn!("Throttled login of user {} for {} seconds.", &payloads.username, retry_after);
+        return Err(AppError::TooManyRequests(retry_after));
+    }
+
     // Captura, se existir, o usuário no banco de dados.
-    let user = match service.get_by_username(&payloads.username).await{
+    let user = match service.get_by_username(&payloads.username).await? {
         Some(data) => data,
         None => {
             warn!("User {} not found in database!", &payloads.username);
-            return HttpResponse::NotFound()
-                .json("Invalid username or password.");
+            // Gasta o mesmo tempo de uma senha incorreta.
+            hasher::dummy_verify_password(&payloads.password);
+            return Err(login_failed(&throttle, settings, &payloads.username, ip.as_deref()).await);
         }
     };
 
     // Valida a senha do usuário.
     if !hasher::is_valid_password(&payloads.password, &user.password) {
-        return HttpResponse::Unauthorized()
-            .json("Invalid username or password.");
+        return Err(login_failed(&throttle, settings, &payloads.username, ip.as_deref()).await);
+    }
+
+    // Falha ao limpar o contador não impede o login, o bloqueio apenas vence depois.
+    let _ = throttle.reset(&throttle::user_key(&user.username)).await;
+
+    // Contas desativadas só são informadas a quem conhece a senha.
+    if !user.is_active {
+        warn!("Refused login of inactive user {}.", &user.username);
+        return Err(AppError::inactive_account());
+    }
+
+    // Atualiza hashes legados ou com custos antigos.
+    if hasher::needs_rehash(&user.password) {
+        match hasher::hash_password(&payloads.password) {
+            Some(hash) => {
+                info!("Upgrading password hash of user {}.", &user.username);
+                service.set_password(&user.username, &hash).await;
+            },
+            None => error!("Can not upgrade password hash of user {}.", &user.username),
+        }
+    }
+
+    // Grava o último login apenas se a conta continuar ativa.
+    let now = DateTime::now();
+    if !service.record_login(&user._id, now).await? {
+        warn!("User {} was deactivated during login.", &user.username);
+        return Err(AppError::inactive_account());
     }
 
+    // Cada login abre uma sessão própria do dispositivo.
+    let session_id = ObjectId::new();
+
     // Tenta gerar o token para o usuário.
-    let token = match hasher::generate_jtw(&user) {
+    let (token, claims) = match hasher::generate_jtw(&user, &session_id) {
         Some(tk) => {
             debug!("Generate access token for user {}.", &user.username);
             tk
         },
         None => {
             error!("Can not generate token for user {}.", &user.username);
-            return HttpResponse::InternalServerError()
-                .json("Can not generate access token!");
+            return Err(AppError::Internal("can not generate access token"));
         }
     };
 
-    // Linka o token no usuário para identificações futuras.
-    service.set_token(&user.username, &token).await;
+    let session = SessionModel {
+        _id: session_id,
+        user: user._id,
+        user_agent: req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        ip,
+        jti: claims.jti,
+        token_expires_at: DateTime::from_millis((claims.exp * 1000) as i64),
+        created_at: now,
+        last_seen: now,
+        expires_at: DateTime::from_millis(now.timestamp_millis() + (settings.refresh_token_ttl * 1000) as i64),
+    };
+
+    sessions.create(&session).await?;
+
+    // A família de tokens de renovação é a própria sessão.
+    let refresh_token = tokens.issue(&user._id, Some(session_id)).await?;
 
-    HttpResponse::Ok().json(Login{token})
+    Ok(HttpResponse::Ok().json(Login{token, refresh_token}))
 }
 
 
-/// Rota para capturar um único usuário.
-#[get("/{user_id}/")]
-pub async fn get(path: web::Path<(String, )>) -> HttpResponse {
-    let loopkup = &path.into_inner().0;
-    let service = UserService::new(MongoService::new().await);
-    let user_id  = match ObjectId::parse_str(loopkup) {
-        Ok(id) => id,
+/// Rota para trocar o token de renovação por um novo par de tokens.
+#[post("/token/refresh/")]
+pub async fn refresh(
+    service: web::Data<UserService>,
+    tokens: web::Data<TokenService>,
+    sessions: web::Data<SessionService>,
+    payloads: web::Json<RefreshPayload>,
+) -> Result<HttpResponse, AppError> {
+    // Rotaciona o token, revogando a família em caso de reuso.
+    let (user_id, session_id, refresh_token) = match tokens.rotate(&payloads.refresh_token).await {
+        Ok(value) => value,
+        Err(RefreshError::Storage) => return Err(RefreshError::Storage.into()),
         Err(e) => {
-            error!("Can not parse user ID {}, cause: {}", loopkup, e);
-            return HttpResponse::BadRequest()
-                .json("Invalid lookup content!");
+            warn!("Refused refresh token, cause: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    let user = match service.get_model_by_id(&user_id).await? {
+        Some(data) => data,
+        None => {
+            warn!("Refresh token owner {} not found in database!", &user_id);
+            return Err(RefreshError::Invalid.into());
         }
     };
 
-    match service.get_by_id(&user_id).await {
+    if !user.is_active {
+        warn!("Refused refresh token of inactive user {}.", &user.username);
+        return Err(AppError::inactive_account());
+    }
+
+    let (token, claims) = match hasher::generate_jtw(&user, &session_id) {
+        Some(tk) => tk,
+        None => {
+            error!("Can not generate token for user {}.", &user.username);
+            return Err(AppError::Internal("can not generate access token"));
+        }
+    };
+
+    // A sessão passa a aceitar apenas o novo token.
+    let expires_at = DateTime::from_millis((claims.exp * 1000) as i64);
+    if !sessions.renew(&session_id, &claims.jti, expires_at).await? {
+        warn!("Session {} of user {} is no longer open.", &session_id, &user.username);
+        return Err(RefreshError::Invalid.into());
+    }
+
+    Ok(HttpResponse::Ok().json(Login{token, refresh_token}))
+}
+
+
+/// Rota para encerrar a sessão do usuário autenticado.
+/// Revoga o token de acesso usado e os tokens de renovação da sessão.
+#[post("/logout/")]
+pub async fn logout(
+    auth: Authenticated,
+    sessions: web::Data<SessionService>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+
+    let session_id = parse_lookup(&principal.claims.sid)?;
+
+    // Sem sessão, ela já foi encerrada por outra requisição.
+    if let Some(session) = sessions.get(&session_id).await? {
+        sessions.terminate(&session).await?;
+    }
+
+    info!("User {} logged out.", &principal.user.username);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para listar as sessões do usuário autenticado.
+#[get("/sessions/")]
+pub async fn own_sessions(
+    auth: Authenticated,
+    sessions: web::Data<SessionService>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+
+    list_sessions(&sessions, &principal.user._id, Some(&principal.claims.sid)).await
+}
+
+
+/// Rota para encerrar uma sessão do usuário autenticado.
+#[delete("/sessions/{session_id}/")]
+pub async fn terminate_own_session(
+    auth: Authenticated,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let session_id = parse_lookup(&path.into_inner().0)?;
+
+    terminate_session(&sessions, &principal.user._id, &session_id).await
+}
+
+
+/// Rota para capturar um único usuário.
+#[get("/{user_id}/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn get(
+    _auth: Authenticated,
+    service: web::Data<UserService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    match service.get_by_id(&user_id).await? {
         Some(user) => {
             debug!("Get user {} in lookup query.", &user.username);
-            HttpResponse::Ok()
-                .json(user)
+            Ok(HttpResponse::Ok()
+                .json(user))
         },
         None => {
             warn!("Not found user by ID {} on data base.", &user_id);
-            HttpResponse::NotFound()
-                .json("User not found.")
+            Err(AppError::NotFound("user"))
         }
     }
 }
+
+
+
+/// Rota para listar os usuários.
+#[get("/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn list(
+    _auth: Authenticated,
+    service: web::Data<UserService>,
+    query: web::Query<ListUsersQuery>,
+) -> Result<HttpResponse, AppError> {
+    let limit = query.limit.clamp(1, 500);
+
+    let users = service.list(query.is_active, query.skip, limit).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+
+/// Rota para cadastrar um usuário.
+#[post("/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn create(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    payloads: web::Json<CreateUserPayload>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let payloads = payloads.into_inner();
+
+    payloads.validate().map_err(AppError::Validation)?;
+
+    if payloads.is_superuser && !principal.user.is_superuser {
+        warn!("User {} tried to create a superuser.", &principal.user.username);
+        return Err(AppError::superuser_required());
+    }
+
+    let password = match hasher::hash_password(&payloads.password) {
+        Some(value) => value,
+        None => {
+            return Err(AppError::Internal("can not hash password"));
+        }
+    };
+    let user = UserModel {
+        _id: ObjectId::new(),
+        username: payloads.username,
+        email: payloads.email,
+        password,
+        first_name: payloads.first_name,
+        last_name: payloads.last_name,
+        is_active: payloads.is_active,
+        is_superuser: payloads.is_superuser,
+        created_at: DateTime::now(),
+        last_login: None,
+    };
+
+    service.create(&user).await?;
+
+    Ok(HttpResponse::Created().json(UserSerialize::from(&user)))
+}
+
+
+/// Rota para atualizar todos os campos de um usuário.
+#[put("/{user_id}/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn update(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<UpdateUserPayload>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+    let payloads = payloads.into_inner();
+
+    payloads.validate().map_err(AppError::Validation)?;
+
+    let mut changes = UserChanges {
+        username: Some(payloads.username),
+        email: Some(payloads.email),
+        first_name: Some(payloads.first_name),
+        last_name: Some(payloads.last_name),
+        is_active: Some(payloads.is_active),
+        is_superuser: Some(payloads.is_superuser),
+        ..Default::default()
+    };
+
+    if let Some(password) = payloads.password {
+        match hasher::hash_password(&password) {
+            Some(value) => changes.password = Some(value),
+            None => {
+                return Err(AppError::Internal("can not hash password"));
+            }
+        };
+    }
+
+    save(&principal, &service, &sessions, &user_id, changes).await
+}
+
+
+/// Rota para atualizar parcialmente um usuário.
+#[patch("/{user_id}/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn partial_update(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+    payloads: web::Json<PatchUserPayload>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+    let payloads = payloads.into_inner();
+
+    payloads.validate().map_err(AppError::Validation)?;
+
+    let mut changes = UserChanges {
+        username: payloads.username,
+        email: payloads.email,
+        first_name: payloads.first_name,
+        last_name: payloads.last_name,
+        is_active: payloads.is_active,
+        is_superuser: payloads.is_superuser,
+        ..Default::default()
+    };
+
+    if let Some(password) = payloads.password {
+        match hasher::hash_password(&password) {
+            Some(value) => changes.password = Some(value),
+            None => {
+                return Err(AppError::Internal("can not hash password"));
+            }
+        };
+    }
+
+    if changes.is_empty() {
+        return Err(AppError::Validation("No fields to update.".to_string()));
+    }
+
+    save(&principal, &service, &sessions, &user_id, changes).await
+}
+
+
+/// Rota para desativar um usuário sem removê-lo.
+#[post("/{user_id}/deactivate/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn deactivate(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    let changes = UserChanges {
+        is_active: Some(false),
+        ..Default::default()
+    };
+
+    save(&principal, &service, &sessions, &user_id, changes).await
+}
+
+
+/// Rota administrativa para encerrar todas as sessões de um usuário.
+#[post("/{user_id}/revoke/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn revoke(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    guard_superuser(&principal, &service, &user_id, None).await?;
+
+    end_sessions(&service, &sessions, &user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota administrativa para listar as sessões de um usuário.
+#[get("/{user_id}/sessions/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn user_sessions(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    guard_superuser(&principal, &service, &user_id, None).await?;
+
+    list_sessions(&sessions, &user_id, None).await
+}
+
+
+/// Rota administrativa para encerrar uma sessão de um usuário.
+#[delete("/{user_id}/sessions/{session_id}/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn terminate_session_of(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let (user_lookup, session_lookup) = path.into_inner();
+    let user_id = parse_lookup(&user_lookup)?;
+    let session_id = parse_lookup(&session_lookup)?;
+
+    guard_superuser(&principal, &service, &user_id, None).await?;
+
+    terminate_session(&sessions, &user_id, &session_id).await
+}
+
+
+/// Rota administrativa para desbloquear o login de um usuário.
+#[post("/{user_id}/unlock/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn unlock(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    throttle: web::Data<ThrottleService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    guard_superuser(&principal, &service, &user_id, None).await?;
+    let user = match service.get_model_by_id(&user_id).await? {
+        Some(value) => value,
+        None => return Err(AppError::NotFound("user")),
+    };
+
+    throttle.reset(&throttle::user_key(&user.username)).await?;
+
+    info!("Unlocked login of user {}.", &user.username);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+
+/// Rota para remover um usuário.
+/// As sessões do usuário são encerradas antes da remoção.
+#[delete("/{user_id}/", wrap = "RequirePermission::new(\"users\")")]
+pub async fn remove(
+    auth: Authenticated,
+    service: web::Data<UserService>,
+    sessions: web::Data<SessionService>,
+    path: web::Path<(String, )>,
+) -> Result<HttpResponse, AppError> {
+    let Authenticated(principal) = auth;
+    let user_id = parse_lookup(&path.into_inner().0)?;
+
+    guard_superuser(&principal, &service, &user_id, None).await?;
+
+    sessions.terminate_all(&user_id).await?;
+
+    match service.delete(&user_id).await? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Err(AppError::NotFound("user")),
+    }
+}
+
+
+/// Grava os campos alterados e monta a resposta da rota.
+/// Desativar o usuário encerra suas sessões na hora.
+async fn save(principal: &Principal, service: &UserService, sessions: &SessionService, user_id: &ObjectId, changes: UserChanges) -> Result<HttpResponse, AppError> {
+    let deactivated = changes.is_active == Some(false);
+
+    guard_superuser(principal, service, user_id, changes.is_superuser).await?;
+
+    match service.update(user_id, &changes).await? {
+        Some(user) => {
+            if deactivated {
+                if let Err(e) = end_sessions(service, sessions, user_id).await {
+                    error!("Can not revoke sessions of deactivated user {}, cause {}", &user.username, e);
+                }
+            }
+
+            Ok(HttpResponse::Ok().json(user))
+        },
+        None => {
+            warn!("Not found user by ID {} on data base.", user_id);
+            Err(AppError::NotFound("user"))
+        },
+    }
+}
+
+
+/// Recusa, a quem não é superusuário, alterar contas de superusuários
+/// ou mudar o `is_superuser` de qualquer conta.
+async fn guard_superuser(principal: &Principal, service: &UserService, user_id: &ObjectId, is_superuser: Option<bool>) -> Result<(), AppError> {
+    if principal.user.is_superuser {
+        return Ok(());
+    }
+
+    let user = match service.get_model_by_id(user_id).await? {
+        Some(value) => value,
+        None => return Err(AppError::NotFound("user")),
+    };
+
+    if user.is_superuser || is_superuser.is_some_and(|value| value != user.is_superuser) {
+        warn!("User {} tried to manage superuser access of {}.", &principal.user.username, &user.username);
+        return Err(AppError::superuser_required());
+    }
+
+    Ok(())
+}
+
+
+/// Conta a falha de login por usuário e por IP e monta o erro genérico.
+async fn login_failed(throttle: &ThrottleService, settings: &Settings, username: &str, ip: Option<&str>) -> AppError {
+    throttle.record_failure(&throttle::user_key(username), settings.login_max_failures, true).await;
+
+    if let Some(value) = ip {
+        throttle.record_failure(&throttle::ip_key(value), settings.login_ip_max_failures, false).await;
+    }
+
+    AppError::invalid_credentials()
+}
+
+
+/// Encerra todas as sessões do usuário.
+async fn end_sessions(service: &UserService, sessions: &SessionService, user_id: &ObjectId) -> Result<(), AppError> {
+    let user = match service.get_model_by_id(user_id).await? {
+        Some(value) => value,
+        None => return Err(AppError::NotFound("user")),
+    };
+
+    sessions.terminate_all(&user._id).await?;
+
+    info!("Revoked sessions of user {}.", &user.username);
+
+    Ok(())
+}
+
+
+/// Lista as sessões do usuário marcando a sessão corrente.
+async fn list_sessions(sessions: &SessionService, user_id: &ObjectId, current: Option<&String>) -> Result<HttpResponse, AppError> {
+    let values: Vec<SessionSerialize> = sessions
+        .list(user_id)
+        .await?
+        .iter()
+        .map(|session| SessionSerialize {
+            current: current == Some(&session._id.to_hex()),
+            ..SessionSerialize::from(session)
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(values))
+}
+
+
+/// Encerra a sessão se ela pertencer ao usuário.
+async fn terminate_session(sessions: &SessionService, user_id: &ObjectId, session_id: &ObjectId) -> Result<HttpResponse, AppError> {
+    let session = match sessions.get(session_id).await? {
+        Some(value) if &value.user == user_id => value,
+        _ => return Err(AppError::NotFound("session")),
+    };
+
+    sessions.terminate(&session).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
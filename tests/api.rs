@@ -0,0 +1,558 @@
+use std::sync::Once;
+use std::{env, fs, process};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::{test, App, Error};
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, Params, PasswordHasher, Version};
+use bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use rand_core::OsRng;
+use ring::rand::SystemRandom;
+use ring::signature::Ed25519KeyPair;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha512};
+
+use easy_mdlwr::AppState;
+use easy_mdlwr::middlewares::auth::Authentication;
+use easy_mdlwr::middlewares::cors::Cors;
+use easy_mdlwr::middlewares::request_id::RequestIdentifier;
+use easy_mdlwr::models::users::UserModel;
+use easy_mdlwr::repositories::Storage;
+#[cfg(feature = "sqlite")]
+use easy_mdlwr::repositories::sql::SqlStorage;
+use easy_mdlwr::settings::Settings;
+use easy_mdlwr::tools::hasher;
+
+
+const ADMIN_PASSWORD: &str = "admin-password";
+
+
+/// Carrega as configurações uma única vez para todos os testes.
+/// Custos baixos do Argon2 deixam o login rápido.
+/// A chave interna é uma Ed25519 gerada para a execução.
+fn settings() -> &'static Settings {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let path = env::temp_dir().join(format!("easy_mdlwr-internal-{}.pem", process::id()));
+
+        fs::write(&path, pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref()))).unwrap();
+
+        let args: Vec<String> = [
+            "--jwt-secret-key", "integration-tests-secret-key-with-enough-bytes",
+            "--argon2-memory-cost", "1024",
+            "--argon2-time-cost", "1",
+            "--internal-jwt-private-key-path", path.to_str().unwrap(),
+            "--internal-jwt-key-id", "internal-tests",
+        ].iter().map(|value| value.to_string()).collect();
+
+        Settings::init(&args).expect("valid test settings");
+    });
+
+    Settings::get()
+}
+
+
+/// Backend em memória, ou o SQLite em memória com `--features sqlite`.
+#[cfg(not(feature = "sqlite"))]
+async fn storage() -> Storage {
+    Storage::memory()
+}
+
+
+/// Backend em memória, ou o SQLite em memória com `--features sqlite`.
+/// Uma única conexão mantém o mesmo banco durante o teste.
+#[cfg(feature = "sqlite")]
+async fn storage() -> Storage {
+    let service = SqlStorage::new("sqlite::memory:", 1).await;
+
+    service.migrate().await;
+    Storage::new(service)
+}
+
+
+/// Sobe a aplicação completa sobre um banco vazio, com um superusuário.
+async fn app() -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    app_on(storage().await).await
+}
+
+
+/// Sobe a aplicação sobre o banco informado, com um superusuário.
+async fn app_on(storage: Storage) -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let settings = settings();
+
+    storage.users.insert(&user("admin", ADMIN_PASSWORD, true)).await.unwrap();
+
+    let state = AppState::new(storage, settings);
+
+    test::init_service(
+        App::new()
+            .wrap(Authentication)
+            .wrap(Cors)
+            .wrap(RequestIdentifier)
+            .configure(|cfg| state.configure(cfg))
+    ).await
+}
+
+
+fn user(username: &str, password: &str, is_superuser: bool) -> UserModel {
+    UserModel {
+        _id: ObjectId::new(),
+        username: username.to_string(),
+        email: format!("{}@example.com", username),
+        password: hasher::hash_password(password).unwrap(),
+        first_name: username.to_string(),
+        last_name: "Tester".to_string(),
+        is_active: true,
+        is_superuser,
+        created_at: DateTime::now(),
+        last_login: None,
+    }
+}
+
+
+/// Executa a requisição e devolve o status, os cabeçalhos e o corpo em JSON.
+async fn call<S, B>(app: &S, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, header::HeaderMap, Value)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let mut request = test::TestRequest::default().method(method).uri(uri);
+
+    if let Some(value) = token {
+        request = request.insert_header((header::AUTHORIZATION, format!("Bearer {}", value)));
+    }
+    if let Some(value) = body {
+        request = request.set_json(value);
+    }
+
+    let response = test::call_service(app, request.to_request()).await;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = test::read_body(response).await;
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    (status, headers, body)
+}
+
+
+async fn login<S, B>(app: &S, username: &str, password: &str) -> Value
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let payload = json!({"username": username, "password": password});
+    let (status, _, body) = call(app, Method::POST, "/api/v1/users/login/", None, Some(payload)).await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body
+}
+
+
+#[actix_web::test]
+async fn login_refresh_and_logout() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str().unwrap();
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, _, sessions) = call(&app, Method::GET, "/api/v1/users/sessions/", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["current"], true);
+
+    // A troca invalida o token de renovação anterior.
+    let payload = json!({"refresh_token": refresh_token});
+    let (status, _, renewed) = call(&app, Method::POST, "/api/v1/users/token/refresh/", None, Some(payload.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = renewed["token"].as_str().unwrap();
+
+    let (status, _, problem) = call(&app, Method::POST, "/api/v1/users/token/refresh/", None, Some(payload)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["code"], "refresh_token_reused");
+
+    // O reuso encerra a sessão, inclusive o token de acesso já renovado.
+    let (status, _, problem) = call(&app, Method::GET, "/api/v1/users/sessions/", Some(token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["code"], "invalid_token");
+
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str().unwrap();
+
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/users/logout/", Some(token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, problem) = call(&app, Method::GET, "/api/v1/users/sessions/", Some(token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["code"], "invalid_token");
+}
+
+
+#[actix_web::test]
+async fn login_upgrades_outdated_hashes() {
+    let storage = Storage::memory();
+    let app = app_on(storage.clone()).await;
+    let legacy: String = Sha512::digest(b"legacy-password")
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let params = Params::new(settings().argon2_memory_cost * 2, 1, 1, None).unwrap();
+    let costly = Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(b"costly-password", &SaltString::generate(&mut OsRng))
+        .unwrap()
+        .to_string();
+
+    for (username, password, hash) in [("legacy", "legacy-password", legacy), ("costly", "costly-password", costly)] {
+        storage.users.insert(&UserModel { password: hash.clone(), ..user(username, password, false) }).await.unwrap();
+
+        login(&app, username, password).await;
+
+        // O hash é regravado com os custos atuais e a senha continua válida.
+        let stored = storage.users.find_by_username(username).await.unwrap().unwrap();
+        assert_ne!(stored.password, hash);
+        assert!(!hasher::needs_rehash(&stored.password));
+        login(&app, username, password).await;
+    }
+}
+
+
+#[actix_web::test]
+async fn invalid_credentials_are_throttled() {
+    let app = app().await;
+    let payload = json!({"username": "admin", "password": "wrong-password"});
+
+    let (status, _, problem) = call(&app, Method::POST, "/api/v1/users/login/", None, Some(payload.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["code"], "invalid_credentials");
+
+    // A primeira falha já impõe um segundo de espera.
+    let (status, headers, problem) = call(&app, Method::POST, "/api/v1/users/login/", None, Some(payload)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(problem["code"], "too_many_attempts");
+    assert!(headers.contains_key(header::RETRY_AFTER));
+}
+
+
+#[actix_web::test]
+async fn manage_users() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+    let payload = json!({
+        "username": "jane",
+        "email": "jane@example.com",
+        "password": "jane-password",
+        "first_name": "Jane",
+        "last_name": "Doe",
+    });
+
+    let (status, _, created) = call(&app, Method::POST, "/api/v1/users/", token, Some(payload.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(created.get("password").is_none());
+    let uri = format!("/api/v1/users/{}/", created["_id"].as_str().unwrap());
+
+    let (status, _, problem) = call(&app, Method::POST, "/api/v1/users/", token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(problem["code"], "conflict");
+
+    let (status, _, users) = call(&app, Method::GET, "/api/v1/users/", token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users.as_array().unwrap().len(), 2);
+
+    let (status, _, updated) = call(&app, Method::PATCH, &uri, token, Some(json!({"last_name": "Roe"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["last_name"], "Roe");
+
+    // Conta desativada não entra mais.
+    login(&app, "jane", "jane-password").await;
+    let (status, _, updated) = call(&app, Method::POST, &format!("{}deactivate/", uri), token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["is_active"], false);
+
+    let payload = json!({"username": "jane", "password": "jane-password"});
+    let (status, _, problem) = call(&app, Method::POST, "/api/v1/users/login/", None, Some(payload)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["code"], "inactive_account");
+
+    let (status, _, _) = call(&app, Method::DELETE, &uri, token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, problem) = call(&app, Method::GET, &uri, token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(problem["code"], "not_found");
+}
+
+
+#[actix_web::test]
+async fn group_permissions_grant_access() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+
+    let (status, _, permission) = call(&app, Method::POST, "/api/v1/permissions/", token, Some(json!({"name": "users"}))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let permission_id = permission["_id"].as_str().unwrap();
+
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/permissions/", token, Some(json!({"name": "users"}))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let payload = json!({
+        "name": "readers",
+        "actions": {"read": true, "write": false, "delete": false},
+        "permissions": [permission_id],
+    });
+    let (status, _, group) = call(&app, Method::POST, "/api/v1/groups/", token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(group["permissions"][0]["name"], "users");
+    let group_id = group["_id"].as_str().unwrap();
+
+    let payload = json!({
+        "username": "reader",
+        "email": "reader@example.com",
+        "password": "reader-password",
+        "first_name": "Reader",
+        "last_name": "Doe",
+    });
+    let (_, _, reader) = call(&app, Method::POST, "/api/v1/users/", token, Some(payload)).await;
+    let reader_id = reader["_id"].as_str().unwrap();
+    let reader_tokens = login(&app, "reader", "reader-password").await;
+    let reader_token = reader_tokens["token"].as_str();
+
+    // Sem grupo, nenhuma permissão.
+    let (status, _, problem) = call(&app, Method::GET, "/api/v1/users/", reader_token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["code"], "permission_denied");
+    assert_eq!(problem["permission"], "users");
+    assert_eq!(problem["action"], "read");
+
+    let uri = format!("/api/v1/groups/{}/users/", group_id);
+    let (status, _, _) = call(&app, Method::POST, &uri, token, Some(json!({"user": reader_id}))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, _) = call(&app, Method::GET, "/api/v1/users/", reader_token, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // O grupo só permite leitura.
+    let payload = json!({"username": "x", "email": "x@example.com", "password": "x-password", "first_name": "X", "last_name": "X"});
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/users/", reader_token, Some(payload)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let uri = format!("/api/v1/groups/{}/users/{}/", group_id, reader_id);
+    let (status, _, _) = call(&app, Method::DELETE, &uri, token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, _) = call(&app, Method::GET, "/api/v1/users/", reader_token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+
+#[actix_web::test]
+async fn only_superusers_grant_superuser() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+
+    let (_, _, permission) = call(&app, Method::POST, "/api/v1/permissions/", token, Some(json!({"name": "users"}))).await;
+    let payload = json!({
+        "name": "user-admins",
+        "actions": {"read": true, "write": true, "delete": true},
+        "permissions": [permission["_id"]],
+    });
+    let (_, _, group) = call(&app, Method::POST, "/api/v1/groups/", token, Some(payload)).await;
+    let payload = json!({
+        "username": "manager",
+        "email": "manager@example.com",
+        "password": "manager-password",
+        "first_name": "Manager",
+        "last_name": "Doe",
+    });
+    let (_, _, manager) = call(&app, Method::POST, "/api/v1/users/", token, Some(payload)).await;
+    let manager_uri = format!("/api/v1/users/{}/", manager["_id"].as_str().unwrap());
+    let uri = format!("/api/v1/groups/{}/users/", group["_id"].as_str().unwrap());
+    call(&app, Method::POST, &uri, token, Some(json!({"user": manager["_id"]}))).await;
+    let manager_tokens = login(&app, "manager", "manager-password").await;
+    let manager_token = manager_tokens["token"].as_str();
+
+    // Cadastra usuários comuns, mas não superusuários.
+    let payload = json!({"username": "root", "email": "root@example.com", "password": "root-password", "first_name": "R", "last_name": "R", "is_superuser": true});
+    let (status, _, problem) = call(&app, Method::POST, "/api/v1/users/", manager_token, Some(payload)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["code"], "superuser_required");
+
+    let payload = json!({"username": "plain", "email": "plain@example.com", "password": "plain-password", "first_name": "P", "last_name": "P"});
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/users/", manager_token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Nem promove a si mesmo.
+    let (status, _, problem) = call(&app, Method::PATCH, &manager_uri, manager_token, Some(json!({"is_superuser": true}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["code"], "superuser_required");
+
+    let (status, _, _) = call(&app, Method::PATCH, &manager_uri, manager_token, Some(json!({"last_name": "Roe"}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Nem altera contas de superusuários.
+    let (_, _, users) = call(&app, Method::GET, "/api/v1/users/?limit=10", token, None).await;
+    let admin = users.as_array().unwrap().iter().find(|user| user["username"] == "admin").unwrap();
+    let admin_uri = format!("/api/v1/users/{}/", admin["_id"].as_str().unwrap());
+    let (status, _, _) = call(&app, Method::PATCH, &admin_uri, manager_token, Some(json!({"password": "taken-over"}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = call(&app, Method::DELETE, &admin_uri, manager_token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Nem vê ou encerra suas sessões, nem desbloqueia seu login.
+    let (_, _, admin_sessions) = call(&app, Method::GET, &format!("{}sessions/", admin_uri), token, None).await;
+    let admin_session_uri = format!("{}sessions/{}/", admin_uri, admin_sessions[0]["_id"].as_str().unwrap());
+    let requests = [
+        (Method::GET, format!("{}sessions/", admin_uri)),
+        (Method::DELETE, admin_session_uri),
+        (Method::POST, format!("{}revoke/", admin_uri)),
+        (Method::POST, format!("{}unlock/", admin_uri)),
+    ];
+    for (method, uri) in requests {
+        let (status, _, problem) = call(&app, method, &uri, manager_token, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+        assert_eq!(problem["code"], "superuser_required");
+    }
+
+    // Mas gerencia as contas comuns.
+    let (status, _, _) = call(&app, Method::GET, &format!("{}sessions/", manager_uri), manager_token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = call(&app, Method::POST, &format!("{}unlock/", manager_uri), manager_token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // A sessão do superusuário segue válida.
+    let (status, _, _) = call(&app, Method::GET, "/api/v1/users/sessions/", token, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+
+#[actix_web::test]
+async fn register_micro_services() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+    let payload = json!({"name": "billing", "host": "http://billing.local:8000/", "routes": ["/billing/"]});
+
+    let (status, _, micro_service) = call(&app, Method::POST, "/api/v1/micro_services/", token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(micro_service["host"], "http://billing.local:8000");
+    assert_eq!(micro_service["routes"][0], "/billing");
+    let micro_service_id = micro_service["_id"].as_str().unwrap();
+
+    // Cada prefixo pertence a um único micro serviço.
+    let payload = json!({"name": "invoices", "host": "http://invoices.local", "routes": ["/billing"]});
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/micro_services/", token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, _, permission) = call(&app, Method::POST, "/api/v1/permissions/", token, Some(json!({"name": "billing"}))).await;
+    let uri = format!("/api/v1/permissions/{}/micro_services/", permission["_id"].as_str().unwrap());
+    let payload = json!({"micro_service": micro_service_id});
+
+    let (status, _, _) = call(&app, Method::POST, &uri, token, Some(payload.clone())).await;
+    assert!(status.is_success());
+    let (status, _, _) = call(&app, Method::POST, &uri, token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let uri = format!("/api/v1/micro_services/{}/", micro_service_id);
+    let payload = json!({"name": "billing", "host": "http://billing.local", "routes": ["/billing", "/payments"]});
+    let (status, _, updated) = call(&app, Method::PUT, &uri, token, Some(payload)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["routes"].as_array().unwrap().len(), 2);
+
+    let (status, _, _) = call(&app, Method::DELETE, &uri, token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, list) = call(&app, Method::GET, "/api/v1/micro_services/", token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+
+#[actix_web::test]
+async fn errors_are_problem_documents() {
+    let app = app().await;
+
+    let (status, headers, problem) = call(&app, Method::GET, "/api/v1/users/", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "application/problem+json");
+    assert_eq!(problem["code"], "authentication_required");
+    assert_eq!(problem["request_id"], headers.get("x-request-id").unwrap().to_str().unwrap());
+
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+
+    let (status, _, problem) = call(&app, Method::GET, "/api/v1/groups/not-an-id/", token, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(problem["code"], "validation_error");
+
+    // Nenhum micro serviço atende o caminho.
+    let (status, _, problem) = call(&app, Method::GET, "/unknown/path", token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(problem["code"], "not_found");
+}
+
+
+#[actix_web::test]
+async fn ip_failures_only_lock_out_at_the_limit() {
+    let app = app().await;
+    let peer = "10.0.0.1:4000".parse().unwrap();
+    let request = |username: &str, password: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/users/login/")
+            .peer_addr(peer)
+            .set_json(json!({"username": username, "password": password}))
+            .to_request()
+    };
+
+    let response = test::call_service(&app, request("nobody", "wrong-password")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // A falha de outro usuário não atrasa quem compartilha o IP.
+    let response = test::call_service(&app, request("admin", ADMIN_PASSWORD)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+
+#[actix_web::test]
+async fn jwks_publishes_the_internal_key() {
+    let app = app().await;
+
+    let (status, _, jwks) = call(&app, Method::GET, "/.well-known/jwks.json", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A chave de acesso é HS384, então só a interna é pública.
+    let keys = jwks["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["kid"], "internal-tests");
+    assert_eq!(keys[0]["alg"], "EdDSA");
+
+    // Os tokens internos apontam para ela pelo `kid`.
+    let token = hasher::generate_internal_jwt(&user("internal", "password", false), "service", Vec::new()).unwrap();
+    let header = jsonwebtoken::decode_header(&token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("internal-tests"));
+    assert_eq!(header.alg, jsonwebtoken::Algorithm::EdDSA);
+}
+
+
+#[actix_web::test]
+async fn proxy_refuses_dot_segments() {
+    let app = app().await;
+    let tokens = login(&app, "admin", ADMIN_PASSWORD).await;
+    let token = tokens["token"].as_str();
+
+    let payload = json!({"name": "billing", "host": "http://127.0.0.1:9", "routes": ["/billing"]});
+    let (status, _, _) = call(&app, Method::POST, "/api/v1/micro_services/", token, Some(payload)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // O cliente HTTP resolveria esses caminhos para fora de `/billing`.
+    for uri in ["/billing/../admin", "/billing/%2e%2E/admin", "/billing/./items", "/billing//items"] {
+        let (status, _, problem) = call(&app, Method::GET, uri, token, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(problem["code"], "validation_error");
+    }
+}